@@ -47,7 +47,7 @@ fn run() -> Vec<u8> {
                 .parameter("[Ljava/lang/String;".to_string())
                 .r#return("V".to_string())
                 .code(CodeBuilder::new().max_locals(1).instructions([
-                    getstatic("java/lang/System", "out", "Ljava/io/PrintStream"),
+                    getstatic("java/lang/System", "out", "Ljava/io/PrintStream;"),
                     iconst(2),
                     iconst(2),
                     invokestatic("Test", "add", "(II)I"),
@@ -66,7 +66,7 @@ fn run() -> Vec<u8> {
 fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function(
         "generate bytecode for adding two numbers with a function",
-        |b| b.iter(|| run()),
+        |b| b.iter(run),
     );
 }
 
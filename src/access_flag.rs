@@ -8,6 +8,10 @@ pub enum AccessFlag {
     Static,
     Final,
     Synchronized,
+    /// A field that is never cached. This shares its value with [AccessFlag::Bridge].
+    Volatile,
+    /// A field that is not serialized. This shares its value with [AccessFlag::Varargs].
+    Transient,
     Bridge,
     Varargs,
     Native,
@@ -25,6 +29,8 @@ impl From<AccessFlag> for u16 {
             AccessFlag::Static => 0x0008,
             AccessFlag::Final => 0x0010,
             AccessFlag::Synchronized => 0x0020,
+            AccessFlag::Volatile => 0x0040,
+            AccessFlag::Transient => 0x0080,
             AccessFlag::Bridge => 0x0040,
             AccessFlag::Varargs => 0x0080,
             AccessFlag::Native => 0x0100,
@@ -0,0 +1,209 @@
+use crate::constant_pool::ConstantPool;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// An annotation on a class member, such as `@Deprecated(since = "9")`.
+/// See https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.7.16
+pub struct Annotation {
+    /// The field descriptor of the annotation type, such as `Ljava/lang/Deprecated;`.
+    pub descriptor: String,
+    pub elements: Vec<(String, ElementValue)>,
+    /// Whether the annotation is visible to reflection. Annotations with `CLASS` retention are
+    /// invisible.
+    pub visible: bool,
+}
+
+impl Annotation {
+    /// Creates a new visible annotation of the type with the given field descriptor.
+    pub fn new(descriptor: impl Into<String>) -> Self {
+        Self {
+            descriptor: descriptor.into(),
+            elements: Vec::new(),
+            visible: true,
+        }
+    }
+
+    /// Sets the value of an element of the annotation.
+    pub fn element(mut self, name: impl Into<String>, value: impl Into<ElementValue>) -> Self {
+        self.elements.push((name.into(), value.into()));
+        self
+    }
+
+    /// Marks the annotation as invisible to reflection, for annotation types with `CLASS`
+    /// retention.
+    pub fn invisible(mut self) -> Self {
+        self.visible = false;
+        self
+    }
+
+    /// Emits the annotation as a byte vector.
+    pub fn emit(&self, constant_pool: &mut ConstantPool) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&constant_pool.insert_string(&self.descriptor).to_be_bytes());
+        bytes.extend_from_slice(&(self.elements.len() as u16).to_be_bytes());
+        for (name, value) in self.elements.iter() {
+            bytes.extend_from_slice(&constant_pool.insert_string(name).to_be_bytes());
+            bytes.extend(value.emit(constant_pool));
+        }
+        bytes
+    }
+}
+
+#[derive(Debug, Clone)]
+/// The value of an element of an [Annotation].
+pub enum ElementValue {
+    Byte(i8),
+    /// A `char`, as a UTF-16 code unit.
+    Char(u16),
+    Double(f64),
+    Float(f32),
+    Int(i32),
+    Long(i64),
+    Short(i16),
+    Boolean(bool),
+    String(String),
+    /// An enum constant, given the field descriptor of the enum type and the name of the constant.
+    Enum {
+        descriptor: String,
+        name: String,
+    },
+    /// A class literal, given as a return descriptor such as `Ljava/lang/String;` or `V`.
+    Class(String),
+    Annotation(Annotation),
+    Array(Vec<ElementValue>),
+}
+
+impl ElementValue {
+    /// Emits the element value as a byte vector.
+    pub fn emit(&self, constant_pool: &mut ConstantPool) -> Vec<u8> {
+        let (tag, index) = match self {
+            ElementValue::Byte(value) => (b'B', constant_pool.insert_integer(*value as i32)),
+            ElementValue::Char(value) => (b'C', constant_pool.insert_integer(*value as i32)),
+            ElementValue::Double(value) => (b'D', constant_pool.insert_double(*value)),
+            ElementValue::Float(value) => (b'F', constant_pool.insert_float(*value)),
+            ElementValue::Int(value) => (b'I', constant_pool.insert_integer(*value)),
+            ElementValue::Long(value) => (b'J', constant_pool.insert_long(*value)),
+            ElementValue::Short(value) => (b'S', constant_pool.insert_integer(*value as i32)),
+            ElementValue::Boolean(value) => (b'Z', constant_pool.insert_integer(*value as i32)),
+            ElementValue::String(value) => (b's', constant_pool.insert_string(value)),
+            ElementValue::Class(descriptor) => (b'c', constant_pool.insert_string(descriptor)),
+            ElementValue::Enum { descriptor, name } => {
+                let mut bytes = vec![b'e'];
+                bytes.extend_from_slice(&constant_pool.insert_string(descriptor).to_be_bytes());
+                bytes.extend_from_slice(&constant_pool.insert_string(name).to_be_bytes());
+                return bytes;
+            }
+            ElementValue::Annotation(annotation) => {
+                let mut bytes = vec![b'@'];
+                bytes.extend(annotation.emit(constant_pool));
+                return bytes;
+            }
+            ElementValue::Array(values) => {
+                let mut bytes = vec![b'['];
+                bytes.extend_from_slice(&(values.len() as u16).to_be_bytes());
+                for value in values {
+                    bytes.extend(value.emit(constant_pool));
+                }
+                return bytes;
+            }
+        };
+
+        let mut bytes = vec![tag];
+        bytes.extend_from_slice(&index.to_be_bytes());
+        bytes
+    }
+}
+
+impl PartialEq for ElementValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (ElementValue::Byte(a), ElementValue::Byte(b)) => a == b,
+            (ElementValue::Char(a), ElementValue::Char(b)) => a == b,
+            (ElementValue::Double(a), ElementValue::Double(b)) => a.to_bits() == b.to_bits(),
+            (ElementValue::Float(a), ElementValue::Float(b)) => a.to_bits() == b.to_bits(),
+            (ElementValue::Int(a), ElementValue::Int(b)) => a == b,
+            (ElementValue::Long(a), ElementValue::Long(b)) => a == b,
+            (ElementValue::Short(a), ElementValue::Short(b)) => a == b,
+            (ElementValue::Boolean(a), ElementValue::Boolean(b)) => a == b,
+            (ElementValue::String(a), ElementValue::String(b)) => a == b,
+            (
+                ElementValue::Enum { descriptor, name },
+                ElementValue::Enum {
+                    descriptor: other_descriptor,
+                    name: other_name,
+                },
+            ) => descriptor == other_descriptor && name == other_name,
+            (ElementValue::Class(a), ElementValue::Class(b)) => a == b,
+            (ElementValue::Annotation(a), ElementValue::Annotation(b)) => a == b,
+            (ElementValue::Array(a), ElementValue::Array(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for ElementValue {}
+
+impl From<i8> for ElementValue {
+    fn from(value: i8) -> Self {
+        ElementValue::Byte(value)
+    }
+}
+
+impl From<f64> for ElementValue {
+    fn from(value: f64) -> Self {
+        ElementValue::Double(value)
+    }
+}
+
+impl From<f32> for ElementValue {
+    fn from(value: f32) -> Self {
+        ElementValue::Float(value)
+    }
+}
+
+impl From<i32> for ElementValue {
+    fn from(value: i32) -> Self {
+        ElementValue::Int(value)
+    }
+}
+
+impl From<i64> for ElementValue {
+    fn from(value: i64) -> Self {
+        ElementValue::Long(value)
+    }
+}
+
+impl From<i16> for ElementValue {
+    fn from(value: i16) -> Self {
+        ElementValue::Short(value)
+    }
+}
+
+impl From<bool> for ElementValue {
+    fn from(value: bool) -> Self {
+        ElementValue::Boolean(value)
+    }
+}
+
+impl From<&str> for ElementValue {
+    fn from(value: &str) -> Self {
+        ElementValue::String(value.to_string())
+    }
+}
+
+impl From<String> for ElementValue {
+    fn from(value: String) -> Self {
+        ElementValue::String(value)
+    }
+}
+
+impl From<Annotation> for ElementValue {
+    fn from(value: Annotation) -> Self {
+        ElementValue::Annotation(value)
+    }
+}
+
+impl From<Vec<ElementValue>> for ElementValue {
+    fn from(values: Vec<ElementValue>) -> Self {
+        ElementValue::Array(values)
+    }
+}
@@ -1,6 +1,43 @@
-use crate::{constant_pool::ConstantPool, instruction::Instruction, Error};
+use std::collections::{BTreeMap, HashMap};
 
-use super::Attribute;
+use crate::{
+    class_hierarchy::ClassHierarchy, constant_pool::ConstantPool, descriptor::slot_size,
+    instruction::{
+        aload, astore, athrow, dup, goto, ifeq, iload, invokevirtual, istore, label, ldc,
+        monitorenter, monitorexit, push_int, switch, Instruction,
+    },
+    label::Label,
+    local::{Local, LocalIndex, Locals},
+    Error,
+};
+
+use super::{
+    frames,
+    try_builder::{exit_to, inline_finally, Inlining, TryBuilder},
+    Attribute, ExceptionTableEntry,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A range of code protected by an exception handler, before its labels are resolved.
+pub(crate) struct TryCatchBlock {
+    pub start: Label,
+    pub end: Label,
+    pub handler: Label,
+    pub catch_type: Option<String>,
+}
+
+#[derive(Clone, Copy)]
+/// Describes the method that a [CodeBuilder] is being built for.
+/// This is needed to compute the `StackMapTable` frames.
+pub struct MethodContext<'a> {
+    /// The name of the class containing the method, in the internal format (`com/example/ExampleClass`).
+    pub class_name: &'a str,
+    pub name: &'a str,
+    pub parameters: &'a [String],
+    pub is_static: bool,
+    /// Used to find the common super class of two types when merging frames.
+    pub hierarchy: &'a dyn ClassHierarchy,
+}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 /// A builder for the `Code` attribute.
@@ -8,8 +45,10 @@ pub struct CodeBuilder {
     max_stack: u16,
     max_locals: u16,
     code: Vec<Instruction>,
-    // exception_table: Vec<ExceptionTableEntry>,
+    exception_table: Vec<TryCatchBlock>,
     attributes: Vec<Attribute>,
+    next_label: u32,
+    locals: Locals,
 }
 
 impl CodeBuilder {
@@ -19,8 +58,10 @@ impl CodeBuilder {
             max_stack: 0,
             max_locals: 0,
             code: Vec::new(),
-            // exception_table: Vec::new(),
+            exception_table: Vec::new(),
             attributes: Vec::new(),
+            next_label: 0,
+            locals: Locals::new(),
         }
     }
 
@@ -32,7 +73,9 @@ impl CodeBuilder {
     }
 
     /// Sets the maximum number of local variables in the code attribute.
-    /// This will **not** be calculated automatically.
+    /// This is raised to the number calculated from the method's parameters and the local variables
+    /// accessed by its instructions if it is smaller, so it only needs to be set to reserve extra
+    /// slots.
     pub fn max_locals(mut self, max_locals: u16) -> Self {
         self.max_locals = max_locals;
         self
@@ -47,55 +90,506 @@ impl CodeBuilder {
         self
     }
 
+    /// Returns the instructions added so far.
+    pub(crate) fn code(&self) -> &[Instruction] {
+        &self.code
+    }
+
+    /// Creates a new [Label] that can be placed into the instructions of this code attribute.
+    pub fn new_label(&mut self) -> Label {
+        let label = Label(self.next_label);
+        self.next_label += 1;
+        label
+    }
+
+    /// Allocates a new local variable of the given type, such as `I` or `Ljava/lang/String;`.
+    /// Locals are placed after the method's parameters and any local variable slots that are used
+    /// directly, and can be passed to load and store instructions in place of a slot index.
+    pub fn new_local(&mut self, descriptor: &str) -> Local {
+        self.locals.allocate(descriptor)
+    }
+
+    /// Starts a new scope for locals. See [CodeBuilder::end_scope].
+    pub fn begin_scope(&mut self) {
+        self.locals.begin_scope();
+    }
+
+    /// Ends the innermost scope for locals. The slots of locals created since the scope began will
+    /// be reused by locals created afterwards, so those locals must no longer be used.
+    pub fn end_scope(&mut self) {
+        self.locals.end_scope();
+    }
+
+    /// Adds an exception handler. Exceptions thrown by the code between `start` (inclusive) and `end`
+    /// (exclusive) that are instances of `catch_type` will jump to `handler`, with the exception as
+    /// the only value on the operand stack. A `catch_type` of [None] catches every exception.
+    /// Handlers are tried in the order they are added.
+    pub fn try_catch(
+        mut self,
+        start: Label,
+        end: Label,
+        handler: Label,
+        catch_type: Option<&str>,
+    ) -> Self {
+        self.add_try_catch(start, end, handler, catch_type.map(str::to_string));
+        self
+    }
+
+    pub(crate) fn add_try_catch(
+        &mut self,
+        start: Label,
+        end: Label,
+        handler: Label,
+        catch_type: Option<String>,
+    ) {
+        self.exception_table.push(TryCatchBlock {
+            start,
+            end,
+            handler,
+            catch_type,
+        });
+    }
+
+    /// Adds a structured `try`/`catch`/`finally` block. See [TryBuilder].
+    pub fn try_block(mut self, block: TryBuilder) -> Self {
+        let instructions = block.lower(&mut self);
+        self.code.extend(instructions);
+        self
+    }
+
+    /// Adds a `synchronized` block, which runs `body` while holding the monitor of the object
+    /// pushed by `lock`.
+    ///
+    /// This generates the same layout as `javac`: the monitor is released before every path that
+    /// leaves `body` (falling off the end, returning, or branching to a label outside the block),
+    /// and a handler that catches every exception releases it and rethrows. The protected range
+    /// covers `body` along with each of those releases, and the handler also protects its own
+    /// `monitorexit`, so an exception there retries the release.
+    pub fn synchronized_block<L, B>(mut self, lock: L, body: B) -> Self
+    where
+        L: IntoIterator<Item = Instruction>,
+        B: IntoIterator<Item = Instruction>,
+    {
+        self.begin_scope();
+        let monitor = self.new_local("Ljava/lang/Object;");
+        let exception = self.new_local("Ljava/lang/Throwable;");
+        let release = [aload(monitor), monitorexit()];
+        let after = self.new_label();
+        let handler = self.new_label();
+        let handler_end = self.new_label();
+
+        // Falling off the end of `body` is made an explicit exit, so that the monitor is released
+        // inside the protected range like any other exit.
+        let mut body = body.into_iter().collect();
+        exit_to(&mut self, &mut body, None, after);
+        let mut ranges = Vec::new();
+        let body = inline_finally(
+            &mut self,
+            body,
+            Some(&release),
+            Inlining::Synchronized,
+            &mut ranges,
+        );
+        ranges.push((handler, handler_end));
+        for (start, end) in ranges {
+            self.add_try_catch(start, end, handler, None);
+        }
+
+        self.code.extend(lock);
+        self.code.extend([dup(), astore(monitor), monitorenter()]);
+        self.code.extend(body);
+        self.code.extend([
+            label(handler),
+            astore(exception),
+            aload(monitor),
+            monitorexit(),
+            label(handler_end),
+            aload(exception),
+            athrow(),
+            label(after),
+        ]);
+        self.end_scope();
+        self
+    }
+
+    /// Adds a switch over the `String` on top of the operand stack, jumping to the label of the
+    /// case equal to it, or to `default` if there is none. If a key appears more than once, its
+    /// first case is used.
+    ///
+    /// This generates the same pattern as `javac`: a switch on the string's `hashCode()`, calls to
+    /// `equals` to find which of the keys with that hash matched, and then a switch on the index
+    /// of the matching case. Like `javac`, a `null` string throws a `NullPointerException`.
+    pub fn string_switch<S: Into<String>>(
+        mut self,
+        cases: impl IntoIterator<Item = (S, Label)>,
+        default: Label,
+    ) -> Self {
+        let mut keys: Vec<String> = Vec::new();
+        let mut targets = Vec::new();
+        for (key, target) in cases {
+            let key = key.into();
+            if !keys.contains(&key) {
+                keys.push(key);
+                targets.push(target);
+            }
+        }
+
+        let mut buckets: BTreeMap<i32, Vec<(i32, String)>> = BTreeMap::new();
+        for (index, key) in keys.into_iter().enumerate() {
+            buckets
+                .entry(string_hash_code(&key))
+                .or_default()
+                .push((index as i32, key));
+        }
+
+        self.begin_scope();
+        let value = self.new_local("Ljava/lang/String;");
+        let index = self.new_local("I");
+        let dispatch = self.new_label();
+
+        let mut hashes = Vec::new();
+        let mut checks = Vec::new();
+        for (hash, bucket) in buckets {
+            let start = self.new_label();
+            hashes.push((hash, start));
+            checks.push(label(start));
+
+            let count = bucket.len();
+            for (position, (case, key)) in bucket.into_iter().enumerate() {
+                let next = match position + 1 == count {
+                    true => dispatch,
+                    false => self.new_label(),
+                };
+                checks.extend([
+                    aload(value),
+                    ldc(key),
+                    invokevirtual("java/lang/String", "equals", "(Ljava/lang/Object;)Z"),
+                    ifeq(next),
+                    push_int(case),
+                    istore(index),
+                    goto(dispatch),
+                ]);
+                if next != dispatch {
+                    checks.push(label(next));
+                }
+            }
+        }
+
+        self.code.extend([
+            astore(value),
+            push_int(-1),
+            istore(index),
+            aload(value),
+            invokevirtual("java/lang/String", "hashCode", "()I"),
+            switch(hashes, dispatch),
+        ]);
+        self.code.extend(checks);
+        self.code.extend([
+            label(dispatch),
+            iload(index),
+            switch((0..).zip(targets), default),
+        ]);
+        self.end_scope();
+        self
+    }
+
     /// Builds the code attribute into its attribute form.
-    pub fn build(self, constant_pool: &mut ConstantPool) -> Result<Attribute, Error> {
+    /// Branches whose target is too far away for a 16-bit offset are automatically widened, and a
+    /// `StackMapTable` is computed for the method described by `method`.
+    /// Returns [Error::CodeTooLarge] if the code does not fit in the 65535 bytes allowed for a
+    /// method.
+    pub fn build(
+        mut self,
+        constant_pool: &mut ConstantPool,
+        method: &MethodContext,
+    ) -> Result<Attribute, Error> {
+        self.resolve_locals(method)?;
+        self.resolve_constants(constant_pool);
+        let (offsets, labels) = self.relax_branches()?;
+        let length = match (self.code.last(), offsets.last()) {
+            (Some(instruction), Some(offset)) => (offset + instruction.size(*offset)) as usize,
+            _ => 0,
+        };
+        if length > u16::MAX as usize {
+            return Err(Error::CodeTooLarge(length));
+        }
+        let analysis = frames::compute_frames(
+            &self.code,
+            &offsets,
+            &labels,
+            &self.exception_table,
+            method,
+        )?;
+        // The frame of each block replacing unreachable code has a `Throwable` on the stack.
         let max_stack = if self.max_stack == 0 {
-            self.calculate_max_stack()
+            let max_stack = self.calculate_max_stack(&offsets, &labels)?;
+            if analysis.unreachable.is_empty() {
+                max_stack
+            } else {
+                max_stack.max(1)
+            }
         } else {
             self.max_stack
         };
+        let max_locals = self.max_locals.max(self.calculate_max_locals(method));
+
+        let mut code: Vec<u8> = self
+            .code
+            .iter()
+            .zip(offsets)
+            .map(|(instruction, offset)| instruction.emit(constant_pool, offset, &labels))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .flatten()
+            .collect();
+
+        for (start, end) in analysis.unreachable.iter() {
+            code[*start as usize..*end as usize - 1].fill(0x00); // nop
+            code[*end as usize - 1] = 0xbf; // athrow
+        }
+
+        let mut exception_table = Vec::new();
+        for block in self.exception_table {
+            let resolve = |label: &Label| labels.get(label).copied().ok_or(Error::UnplacedLabel);
+            let (start, end, handler) = (
+                resolve(&block.start)?,
+                resolve(&block.end)?,
+                resolve(&block.handler)?,
+            );
+
+            // Unreachable code is replaced with an `athrow` whose frame has no locals, which would
+            // not match the handler's frame, so it is excluded from the protected range.
+            let mut ranges = vec![(start, end)];
+            for (dead_start, dead_end) in analysis.unreachable.iter() {
+                ranges = ranges
+                    .into_iter()
+                    .flat_map(|(start, end)| {
+                        [(start, end.min(*dead_start)), (start.max(*dead_end), end)]
+                    })
+                    .collect();
+            }
+
+            for (start, end) in ranges.into_iter().filter(|(start, end)| start < end) {
+                exception_table.push(ExceptionTableEntry {
+                    start_pc: start as u16,
+                    end_pc: end as u16,
+                    handler_pc: handler as u16,
+                    catch_type: block.catch_type.clone(),
+                });
+            }
+        }
+
+        let mut attributes = self.attributes;
+        if !analysis.frames.is_empty() {
+            attributes.push(Attribute::StackMapTable {
+                entries: analysis.frames,
+            });
+        }
 
         Ok(Attribute::Code {
             max_stack,
-            max_locals: self.max_locals,
-            code: self
-                .code
-                .into_iter()
-                .map(|instruction| instruction.emit(constant_pool))
-                .collect::<Result<Vec<_>, _>>()?
-                .into_iter()
-                .flatten()
-                .collect(),
-            // exception_table: self.exception_table,
-            attributes: self.attributes,
+            max_locals,
+            code,
+            exception_table,
+            attributes,
         })
     }
 
-    fn calculate_max_stack(&self) -> u16 {
-        let mut max_stack = 0i32;
-        let mut stack = 0;
-
-        for code in self.code.iter() {
-            match code {
-                Instruction::aload { .. } => stack += 1,
-                Instruction::getstatic { .. } => stack += 1,
-                Instruction::iadd => stack -= 1,
-                Instruction::iconst { .. } => stack += 1,
-                Instruction::iload { .. } => stack += 1,
-                Instruction::invokespecial { .. } => stack -= 1,
-                Instruction::invokestatic { .. } => stack -= 1,
-                Instruction::invokevirtual { .. } => stack -= 1,
-                Instruction::ireturn => stack -= 1,
-                Instruction::r#return => stack -= 1,
+    /// Computes the offset of every instruction, and the position of every placed label.
+    fn layout(&self) -> Result<(Vec<u32>, HashMap<Label, u32>), Error> {
+        let mut offsets = Vec::with_capacity(self.code.len());
+        let mut labels = HashMap::new();
+        let mut offset = 0;
+
+        for instruction in self.code.iter() {
+            if let Instruction::label { label } = instruction {
+                if labels.insert(*label, offset).is_some() {
+                    return Err(Error::DuplicateLabel);
+                }
+            }
+
+            offsets.push(offset);
+            offset += instruction.size(offset);
+        }
+
+        Ok((offsets, labels))
+    }
+
+    /// Lays out the code, rewriting any branch whose target is out of the range of a 16-bit offset
+    /// until the layout is stable. `goto` and `jsr` become `goto_w` and `jsr_w`, and conditional
+    /// branches are inverted to jump over a `goto_w` to the original target.
+    fn relax_branches(&mut self) -> Result<(Vec<u32>, HashMap<Label, u32>), Error> {
+        loop {
+            let (offsets, labels) = self.layout()?;
+            let mut relaxed = Vec::with_capacity(self.code.len());
+            let mut changed = false;
+
+            for (instruction, offset) in std::mem::take(&mut self.code).into_iter().zip(&offsets) {
+                let target = match instruction {
+                    Instruction::goto_w { .. } | Instruction::jsr_w { .. } => None,
+                    _ => instruction.branch_target(),
+                };
+
+                let in_range = match target {
+                    Some(target) => {
+                        let target = *labels.get(&target).ok_or(Error::UnplacedLabel)?;
+                        i16::try_from(target as i64 - *offset as i64).is_ok()
+                    }
+                    None => true,
+                };
+
+                if in_range {
+                    relaxed.push(instruction);
+                    continue;
+                }
+
+                changed = true;
+                let target = target.unwrap();
+                match instruction {
+                    Instruction::goto { .. } => relaxed.push(Instruction::goto_w { target }),
+                    Instruction::jsr { .. } => relaxed.push(Instruction::jsr_w { target }),
+                    _ => {
+                        let skip = self.new_label();
+                        relaxed.push(instruction.inverted(skip).unwrap());
+                        relaxed.push(Instruction::goto_w { target });
+                        relaxed.push(Instruction::label { label: skip });
+                    }
+                }
+            }
+
+            self.code = relaxed;
+
+            if !changed {
+                return Ok((offsets, labels));
+            }
+        }
+    }
+
+    /// Gives every [Local] its slot, after the method's parameters and any slots used directly.
+    /// Returns [Error::LocalTypeMismatch] if a [Local] is accessed by an instruction for a
+    /// different type.
+    fn resolve_locals(&mut self, method: &MethodContext) -> Result<(), Error> {
+        let base = self.calculate_max_locals(method);
+
+        for instruction in self.code.iter_mut() {
+            if let Some(LocalIndex::Local(local)) = instruction.local_index_mut().copied() {
+                if instruction.local_type() != Some(local.local_type()) {
+                    return Err(Error::LocalTypeMismatch(
+                        Box::new(instruction.clone()),
+                        local.local_type(),
+                    ));
+                }
+            }
+            if let Some(index) = instruction.local_index_mut() {
+                index.resolve(base)?;
             }
+        }
+
+        Ok(())
+    }
 
-            if stack > max_stack {
-                max_stack = stack;
+    /// Adds every constant loaded by `ldc` to the constant pool, replacing the `ldc` with `ldc_w` if
+    /// its index does not fit in a byte.
+    fn resolve_constants(&mut self, constant_pool: &mut ConstantPool) {
+        for instruction in self.code.iter_mut() {
+            if let Instruction::ldc { constant } = instruction {
+                if constant_pool.insert_constant(constant) > u8::MAX as u16 {
+                    *instruction = Instruction::ldc_w {
+                        constant: constant.clone(),
+                    };
+                }
             }
         }
+    }
+
+    /// Computes the number of local variable slots needed for `this`, the method's parameters, and
+    /// every local variable accessed by the code.
+    fn calculate_max_locals(&self, method: &MethodContext) -> u16 {
+        let parameters: u16 = method
+            .parameters
+            .iter()
+            .map(|parameter| slot_size(parameter))
+            .sum::<u16>()
+            + if method.is_static { 0 } else { 1 };
 
-        max_stack.try_into().unwrap_or(0)
+        self.code
+            .iter()
+            .map(Instruction::locals_used)
+            .fold(parameters, u16::max)
     }
+
+    /// Computes the maximum depth of the operand stack, following every branch and exception
+    /// handler from the start of the code.
+    fn calculate_max_stack(
+        &self,
+        offsets: &[u32],
+        labels: &HashMap<Label, u32>,
+    ) -> Result<u16, Error> {
+        let indices: HashMap<Label, usize> = self
+            .code
+            .iter()
+            .enumerate()
+            .filter_map(|(index, instruction)| match instruction {
+                Instruction::label { label } => Some((*label, index)),
+                _ => None,
+            })
+            .collect();
+        let index_of = |label: &Label| indices.get(label).copied().ok_or(Error::UnplacedLabel);
+        let offset_of = |label: &Label| labels.get(label).copied().ok_or(Error::UnplacedLabel);
+
+        let mut handlers = Vec::new();
+        for block in self.exception_table.iter() {
+            handlers.push((
+                offset_of(&block.start)?..offset_of(&block.end)?,
+                index_of(&block.handler)?,
+            ));
+        }
+
+        let mut visited = vec![false; self.code.len()];
+        let mut worklist = vec![(0, 0u16)];
+        let mut max_stack = 0;
+
+        while let Some((mut index, mut depth)) = worklist.pop() {
+            while index < self.code.len() && !visited[index] {
+                visited[index] = true;
+                let instruction = &self.code[index];
+                let offset = offsets[index];
+
+                for (range, handler) in handlers.iter() {
+                    if instruction.size(offset) > 0 && range.contains(&offset) {
+                        worklist.push((*handler, 1));
+                        max_stack = max_stack.max(1);
+                    }
+                }
+
+                let (pops, pushes) = instruction.stack_effect()?;
+                depth = depth
+                    .checked_sub(pops)
+                    .ok_or(Error::StackUnderflow(offset))?
+                    + pushes;
+                max_stack = max_stack.max(depth);
+
+                for target in instruction.branch_targets() {
+                    worklist.push((index_of(&target)?, depth));
+                }
+
+                if !instruction.falls_through() {
+                    break;
+                }
+
+                index += 1;
+            }
+        }
+
+        Ok(max_stack)
+    }
+}
+
+/// Computes `String.hashCode()` for `value`, which hashes its UTF-16 code units.
+fn string_hash_code(value: &str) -> i32 {
+    value
+        .encode_utf16()
+        .fold(0i32, |hash, unit| hash.wrapping_mul(31).wrapping_add(unit as i32))
 }
 
 impl Default for CodeBuilder {
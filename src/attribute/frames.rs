@@ -0,0 +1,788 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::{
+    class_hierarchy::ClassHierarchy, consts, descriptor::MethodDescriptor,
+    instruction::Instruction, label::Label, Error,
+};
+
+use super::{
+    code::{MethodContext, TryCatchBlock},
+    stack_map_table::{StackMapFrame, VerificationType},
+};
+
+/// The result of running the frame computation over a method's code.
+pub(crate) struct FrameAnalysis {
+    /// The frames to emit in the `StackMapTable` attribute, in order.
+    pub frames: Vec<StackMapFrame>,
+    /// Ranges of unreachable code as `(start, end)` byte offsets. These must be replaced with
+    /// `nop`s followed by an `athrow`, as the verifier still checks unreachable code.
+    pub unreachable: Vec<(u32, u32)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The types of the local variables and operand stack at a point in the code.
+/// Long and double locals take two slots, the second of which is [VerificationType::Top].
+/// Stack entries are not split, so a long on the stack is a single entry.
+struct Frame {
+    locals: Vec<VerificationType>,
+    stack: Vec<VerificationType>,
+}
+
+impl Frame {
+    /// Builds the implicit initial frame of a method from its descriptor.
+    fn initial(method: &MethodContext) -> Result<Self, Error> {
+        let mut locals = Vec::new();
+
+        if !method.is_static {
+            if method.name == "<init>" && method.class_name != consts::OBJECT_CLASS_NAME {
+                locals.push(VerificationType::UninitializedThis);
+            } else {
+                locals.push(VerificationType::Object(method.class_name.to_string()));
+            }
+        }
+
+        for parameter in method.parameters {
+            let ty = VerificationType::from_descriptor(parameter)?;
+            let wide = ty.is_wide();
+            locals.push(ty);
+            if wide {
+                locals.push(VerificationType::Top);
+            }
+        }
+
+        Ok(Self {
+            locals,
+            stack: Vec::new(),
+        })
+    }
+
+    fn push(&mut self, ty: VerificationType) {
+        self.stack.push(ty);
+    }
+
+    fn pop(&mut self, offset: u32) -> Result<VerificationType, Error> {
+        self.stack.pop().ok_or(Error::StackUnderflow(offset))
+    }
+
+    fn pop_n(&mut self, count: usize, offset: u32) -> Result<(), Error> {
+        for _ in 0..count {
+            self.pop(offset)?;
+        }
+        Ok(())
+    }
+
+    /// Pops values taking up exactly `slots` stack slots, returning them in stack order.
+    /// This fails if a `long` or `double` would have to be split.
+    fn pop_slots(&mut self, slots: u16, offset: u32) -> Result<Vec<VerificationType>, Error> {
+        let mut values = Vec::new();
+        let mut popped = 0;
+        while popped < slots {
+            let ty = self.pop(offset)?;
+            popped += if ty.is_wide() { 2 } else { 1 };
+            values.insert(0, ty);
+        }
+
+        if popped != slots {
+            return Err(Error::StackTypeMismatch(offset));
+        }
+        Ok(values)
+    }
+
+    /// Duplicates the values in the top `top` slots, inserting the copy beneath the `under` slots
+    /// below them, as done by the `dup` family of instructions.
+    fn duplicate(&mut self, top: u16, under: u16, offset: u32) -> Result<(), Error> {
+        let values = self.pop_slots(top, offset)?;
+        let below = self.pop_slots(under, offset)?;
+        self.stack.extend(values.iter().cloned());
+        self.stack.extend(below);
+        self.stack.extend(values);
+        Ok(())
+    }
+
+    fn load(&self, index: usize) -> VerificationType {
+        self.locals
+            .get(index)
+            .cloned()
+            .unwrap_or(VerificationType::Top)
+    }
+
+    /// Stores a value into a local, invalidating any `long` or `double` whose second slot it
+    /// overwrites.
+    fn store(&mut self, index: usize, ty: VerificationType) {
+        let size = if ty.is_wide() { 2 } else { 1 };
+        if self.locals.len() < index + size {
+            self.locals.resize(index + size, VerificationType::Top);
+        }
+
+        if index > 0 && self.locals[index - 1].is_wide() {
+            self.locals[index - 1] = VerificationType::Top;
+        }
+
+        self.locals[index] = ty;
+        if size == 2 {
+            self.locals[index + 1] = VerificationType::Top;
+        }
+    }
+
+    /// Replaces every occurrence of an uninitialized type, once its constructor has been called.
+    fn initialize(&mut self, uninitialized: &VerificationType, initialized: VerificationType) {
+        for ty in self.locals.iter_mut().chain(self.stack.iter_mut()) {
+            if ty == uninitialized {
+                *ty = initialized.clone();
+            }
+        }
+    }
+
+    /// Merges another frame into this one, returning whether this frame changed.
+    fn merge(
+        &mut self,
+        other: &Frame,
+        offset: u32,
+        hierarchy: &dyn ClassHierarchy,
+    ) -> Result<bool, Error> {
+        if self.stack.len() != other.stack.len() {
+            return Err(Error::StackHeightMismatch(offset));
+        }
+
+        let mut changed = false;
+
+        for (ty, other) in self.stack.iter_mut().zip(&other.stack) {
+            let merged =
+                merge_types(ty, other, hierarchy)?.ok_or(Error::StackTypeMismatch(offset))?;
+            if merged != *ty {
+                *ty = merged;
+                changed = true;
+            }
+        }
+
+        let length = self.locals.len().max(other.locals.len());
+        self.locals.resize(length, VerificationType::Top);
+        for (index, ty) in self.locals.iter_mut().enumerate() {
+            let other = other.locals.get(index).unwrap_or(&VerificationType::Top);
+            let merged = merge_types(ty, other, hierarchy)?.unwrap_or(VerificationType::Top);
+            if merged != *ty {
+                *ty = merged;
+                changed = true;
+            }
+        }
+
+        Ok(changed)
+    }
+
+    /// Returns the locals in the form used by the `StackMapTable` attribute, where long and double
+    /// take a single entry and trailing unused locals are dropped.
+    fn compressed_locals(&self) -> Vec<VerificationType> {
+        let mut end = self.locals.len();
+        while end > 0 && self.locals[end - 1] == VerificationType::Top {
+            end -= 1;
+        }
+
+        let mut locals = Vec::new();
+        let mut index = 0;
+        while index < end {
+            let ty = self.locals[index].clone();
+            index += if ty.is_wide() { 2 } else { 1 };
+            locals.push(ty);
+        }
+
+        locals
+    }
+}
+
+/// Merges two verification types, returning [None] if they have no common type.
+/// Two different classes are merged to their common super class, as given by `hierarchy`.
+fn merge_types(
+    a: &VerificationType,
+    b: &VerificationType,
+    hierarchy: &dyn ClassHierarchy,
+) -> Result<Option<VerificationType>, Error> {
+    Ok(match (a, b) {
+        _ if a == b => Some(a.clone()),
+        (VerificationType::Null, VerificationType::Object(_)) => Some(b.clone()),
+        (VerificationType::Object(_), VerificationType::Null) => Some(a.clone()),
+        (VerificationType::Object(a), VerificationType::Object(b)) => {
+            Some(VerificationType::Object(merge_references(a, b, hierarchy)?))
+        }
+        _ => None,
+    })
+}
+
+/// Merges two class names or array descriptors into their most specific common type.
+/// Arrays of references are merged element-wise, and anything else mixing arrays becomes
+/// `java/lang/Object`.
+fn merge_references(a: &str, b: &str, hierarchy: &dyn ClassHierarchy) -> Result<String, Error> {
+    if a == b {
+        return Ok(a.to_string());
+    }
+
+    let element_class = |element: &str| -> Option<String> {
+        if element.starts_with('[') {
+            Some(element.to_string())
+        } else {
+            element
+                .strip_prefix('L')
+                .and_then(|element| element.strip_suffix(';'))
+                .map(str::to_string)
+        }
+    };
+
+    Ok(match (a.strip_prefix('['), b.strip_prefix('[')) {
+        (None, None) => hierarchy.common_super_class(a, b)?,
+        (Some(a), Some(b)) => match (element_class(a), element_class(b)) {
+            (Some(a), Some(b)) => {
+                let merged = merge_references(&a, &b, hierarchy)?;
+                if merged.starts_with('[') {
+                    format!("[{}", merged)
+                } else {
+                    format!("[L{};", merged)
+                }
+            }
+            _ => consts::OBJECT_CLASS_NAME.to_string(),
+        },
+        _ => consts::OBJECT_CLASS_NAME.to_string(),
+    })
+}
+
+/// Computes the `StackMapTable` frames of a method by abstract interpretation of its code.
+/// `offsets` and `labels` are the result of laying out the code.
+pub(crate) fn compute_frames(
+    code: &[Instruction],
+    offsets: &[u32],
+    labels: &HashMap<Label, u32>,
+    exception_table: &[TryCatchBlock],
+    method: &MethodContext,
+) -> Result<FrameAnalysis, Error> {
+    // Labels are resolved to the first real instruction at or after them, so that several labels
+    // placed at the same position share a frame.
+    let mut label_indices = HashMap::new();
+    for (index, instruction) in code.iter().enumerate().rev() {
+        if let Instruction::label { label } = instruction {
+            let target = code[index..]
+                .iter()
+                .position(|i| !matches!(i, Instruction::label { .. }))
+                .map(|position| index + position)
+                .unwrap_or(code.len());
+            label_indices.insert(*label, target);
+        }
+    }
+    let target_index = |label: &Label| -> Result<usize, Error> {
+        label_indices
+            .get(label)
+            .copied()
+            .filter(|_| labels.contains_key(label))
+            .ok_or(Error::UnplacedLabel)
+    };
+
+    let mut targets = HashSet::new();
+    for instruction in code {
+        for target in instruction.branch_targets() {
+            targets.insert(target_index(&target)?);
+        }
+    }
+
+    // Each handler is resolved to its protected byte range, its index, and the type on its stack.
+    let mut handlers = Vec::new();
+    for block in exception_table {
+        let resolve = |label: &Label| labels.get(label).copied().ok_or(Error::UnplacedLabel);
+        let handler = target_index(&block.handler)?;
+        targets.insert(handler);
+        handlers.push((
+            resolve(&block.start)?..resolve(&block.end)?,
+            handler,
+            VerificationType::Object(
+                block
+                    .catch_type
+                    .clone()
+                    .unwrap_or_else(|| "java/lang/Throwable".to_string()),
+            ),
+        ));
+    }
+
+    let mut states: Vec<Option<Frame>> = vec![None; code.len()];
+    let mut reached = vec![false; code.len()];
+    let mut worklist = BTreeSet::new();
+
+    if !code.is_empty() {
+        states[0] = Some(Frame::initial(method)?);
+        worklist.insert(0);
+    }
+
+    while let Some(start) = worklist.pop_first() {
+        let mut frame = states[start].clone().unwrap();
+        let mut index = start;
+
+        loop {
+            if index == code.len() {
+                return Err(Error::CodeFallsOffEnd);
+            }
+
+            if index != start && targets.contains(&index) {
+                merge_into(&mut states, &mut worklist, index, &frame, offsets, method)?;
+                break;
+            }
+
+            reached[index] = true;
+            let instruction = &code[index];
+            let before = frame.locals.clone();
+            execute(instruction, &mut frame, offsets[index], method)?;
+
+            // Handlers must accept the locals both before and after any instruction they protect.
+            for (range, handler, catch_type) in handlers.iter() {
+                if instruction.size(offsets[index]) == 0 || !range.contains(&offsets[index]) {
+                    continue;
+                }
+
+                for locals in [&before, &frame.locals] {
+                    let handler_frame = Frame {
+                        locals: locals.clone(),
+                        stack: vec![catch_type.clone()],
+                    };
+                    merge_into(
+                        &mut states,
+                        &mut worklist,
+                        *handler,
+                        &handler_frame,
+                        offsets,
+                        method,
+                    )?;
+                }
+            }
+
+            for target in instruction.branch_targets() {
+                let target = target_index(&target)?;
+                merge_into(&mut states, &mut worklist, target, &frame, offsets, method)?;
+            }
+
+            if !instruction.falls_through() {
+                break;
+            }
+
+            index += 1;
+        }
+    }
+
+    let mut unreachable: Vec<(u32, u32)> = Vec::new();
+    for (index, instruction) in code.iter().enumerate() {
+        if reached[index] || instruction.size(offsets[index]) == 0 {
+            continue;
+        }
+
+        let start = offsets[index];
+        let end = start + instruction.size(start);
+        match unreachable.last_mut() {
+            Some(range) if range.1 == start => range.1 = end,
+            _ => unreachable.push((start, end)),
+        }
+    }
+
+    let mut points: Vec<(u32, Frame)> = targets
+        .iter()
+        .filter(|index| reached.get(**index) == Some(&true))
+        .map(|index| (offsets[*index], states[*index].clone().unwrap()))
+        .collect();
+    for (start, _) in unreachable.iter() {
+        points.push((
+            *start,
+            Frame {
+                locals: Vec::new(),
+                stack: vec![VerificationType::Object("java/lang/Throwable".to_string())],
+            },
+        ));
+    }
+    points.sort_by_key(|(offset, _)| *offset);
+
+    let mut frames = Vec::new();
+    let mut previous_locals = Frame::initial(method)?.compressed_locals();
+    let mut previous_offset: Option<u32> = None;
+
+    for (offset, frame) in points {
+        let offset_delta = match previous_offset {
+            Some(previous) => offset - previous - 1,
+            None => offset,
+        } as u16;
+        previous_offset = Some(offset);
+
+        let locals = frame.compressed_locals();
+        frames.push(encode_frame(
+            offset_delta,
+            &previous_locals,
+            &locals,
+            frame.stack,
+        ));
+        previous_locals = locals;
+    }
+
+    Ok(FrameAnalysis {
+        frames,
+        unreachable,
+    })
+}
+
+/// Merges `frame` into the state at `index`, queueing it for analysis if it changed.
+fn merge_into(
+    states: &mut [Option<Frame>],
+    worklist: &mut BTreeSet<usize>,
+    index: usize,
+    frame: &Frame,
+    offsets: &[u32],
+    method: &MethodContext,
+) -> Result<(), Error> {
+    if index == states.len() {
+        return Err(Error::CodeFallsOffEnd);
+    }
+
+    let changed = match &mut states[index] {
+        Some(existing) => existing.merge(frame, offsets[index], method.hierarchy)?,
+        None => {
+            states[index] = Some(frame.clone());
+            true
+        }
+    };
+
+    if changed {
+        worklist.insert(index);
+    }
+
+    Ok(())
+}
+
+/// Chooses the most compact encoding of a frame relative to the locals of the previous frame.
+fn encode_frame(
+    offset_delta: u16,
+    previous_locals: &[VerificationType],
+    locals: &[VerificationType],
+    stack: Vec<VerificationType>,
+) -> StackMapFrame {
+    if locals == previous_locals {
+        match stack.len() {
+            0 => return StackMapFrame::Same { offset_delta },
+            1 => {
+                return StackMapFrame::SameLocals1StackItem {
+                    offset_delta,
+                    stack: stack.into_iter().next().unwrap(),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if stack.is_empty() {
+        if locals.len() < previous_locals.len()
+            && previous_locals.len() - locals.len() <= 3
+            && previous_locals.starts_with(locals)
+        {
+            return StackMapFrame::Chop {
+                offset_delta,
+                count: (previous_locals.len() - locals.len()) as u8,
+            };
+        }
+
+        if locals.len() > previous_locals.len()
+            && locals.len() - previous_locals.len() <= 3
+            && locals.starts_with(previous_locals)
+        {
+            return StackMapFrame::Append {
+                offset_delta,
+                locals: locals[previous_locals.len()..].to_vec(),
+            };
+        }
+    }
+
+    StackMapFrame::Full {
+        offset_delta,
+        locals: locals.to_vec(),
+        stack,
+    }
+}
+
+/// Applies the effect of an instruction to the types in a frame.
+fn execute(
+    instruction: &Instruction,
+    frame: &mut Frame,
+    offset: u32,
+    method: &MethodContext,
+) -> Result<(), Error> {
+    match instruction {
+        Instruction::label { .. } => {}
+        Instruction::aload { index } => frame.push(frame.load(index.slot()? as usize)),
+        Instruction::iload { .. }
+        | Instruction::iconst { .. }
+        | Instruction::bipush { .. }
+        | Instruction::sipush { .. } => frame.push(VerificationType::Integer),
+        Instruction::lconst_0 | Instruction::lconst_1 => frame.push(VerificationType::Long),
+        Instruction::fconst_0 | Instruction::fconst_1 | Instruction::fconst_2 => {
+            frame.push(VerificationType::Float)
+        }
+        Instruction::dconst_0 | Instruction::dconst_1 => frame.push(VerificationType::Double),
+        Instruction::ldc { constant }
+        | Instruction::ldc_w { constant }
+        | Instruction::ldc2_w { constant } => {
+            frame.push(VerificationType::from_descriptor(&constant.descriptor())?)
+        }
+        Instruction::lload { .. } => frame.push(VerificationType::Long),
+        Instruction::fload { .. } => frame.push(VerificationType::Float),
+        Instruction::dload { .. } => frame.push(VerificationType::Double),
+        Instruction::istore { index }
+        | Instruction::lstore { index }
+        | Instruction::fstore { index }
+        | Instruction::dstore { index }
+        | Instruction::astore { index } => {
+            let ty = frame.pop(offset)?;
+            frame.store(index.slot()? as usize, ty);
+        }
+        Instruction::iadd
+        | Instruction::isub
+        | Instruction::imul
+        | Instruction::idiv
+        | Instruction::irem
+        | Instruction::ishl
+        | Instruction::ishr
+        | Instruction::iushr
+        | Instruction::iand
+        | Instruction::ior
+        | Instruction::ixor => {
+            frame.pop_n(2, offset)?;
+            frame.push(VerificationType::Integer);
+        }
+        Instruction::ladd
+        | Instruction::lsub
+        | Instruction::lmul
+        | Instruction::ldiv
+        | Instruction::lrem
+        | Instruction::lshl
+        | Instruction::lshr
+        | Instruction::lushr
+        | Instruction::land
+        | Instruction::lor
+        | Instruction::lxor => {
+            frame.pop_n(2, offset)?;
+            frame.push(VerificationType::Long);
+        }
+        Instruction::fadd
+        | Instruction::fsub
+        | Instruction::fmul
+        | Instruction::fdiv
+        | Instruction::frem => {
+            frame.pop_n(2, offset)?;
+            frame.push(VerificationType::Float);
+        }
+        Instruction::dadd
+        | Instruction::dsub
+        | Instruction::dmul
+        | Instruction::ddiv
+        | Instruction::drem => {
+            frame.pop_n(2, offset)?;
+            frame.push(VerificationType::Double);
+        }
+        Instruction::ineg => {
+            frame.pop_n(1, offset)?;
+            frame.push(VerificationType::Integer);
+        }
+        Instruction::lneg => {
+            frame.pop_n(1, offset)?;
+            frame.push(VerificationType::Long);
+        }
+        Instruction::fneg => {
+            frame.pop_n(1, offset)?;
+            frame.push(VerificationType::Float);
+        }
+        Instruction::dneg => {
+            frame.pop_n(1, offset)?;
+            frame.push(VerificationType::Double);
+        }
+        Instruction::iinc { .. } => {}
+        Instruction::i2l | Instruction::f2l | Instruction::d2l => {
+            frame.pop_n(1, offset)?;
+            frame.push(VerificationType::Long);
+        }
+        Instruction::i2f | Instruction::l2f | Instruction::d2f => {
+            frame.pop_n(1, offset)?;
+            frame.push(VerificationType::Float);
+        }
+        Instruction::i2d | Instruction::l2d | Instruction::f2d => {
+            frame.pop_n(1, offset)?;
+            frame.push(VerificationType::Double);
+        }
+        Instruction::l2i
+        | Instruction::f2i
+        | Instruction::d2i
+        | Instruction::i2b
+        | Instruction::i2c
+        | Instruction::i2s => {
+            frame.pop_n(1, offset)?;
+            frame.push(VerificationType::Integer);
+        }
+        Instruction::lcmp
+        | Instruction::fcmpl
+        | Instruction::fcmpg
+        | Instruction::dcmpl
+        | Instruction::dcmpg => {
+            frame.pop_n(2, offset)?;
+            frame.push(VerificationType::Integer);
+        }
+        Instruction::ifeq { .. }
+        | Instruction::ifne { .. }
+        | Instruction::iflt { .. }
+        | Instruction::ifge { .. }
+        | Instruction::ifgt { .. }
+        | Instruction::ifle { .. }
+        | Instruction::ifnull { .. }
+        | Instruction::ifnonnull { .. }
+        | Instruction::tableswitch { .. }
+        | Instruction::lookupswitch { .. } => frame.pop_n(1, offset)?,
+        Instruction::if_icmpeq { .. }
+        | Instruction::if_icmpne { .. }
+        | Instruction::if_icmplt { .. }
+        | Instruction::if_icmpge { .. }
+        | Instruction::if_icmpgt { .. }
+        | Instruction::if_icmple { .. }
+        | Instruction::if_acmpeq { .. }
+        | Instruction::if_acmpne { .. } => frame.pop_n(2, offset)?,
+        Instruction::goto { .. } | Instruction::goto_w { .. } => {}
+        Instruction::jsr { .. } | Instruction::jsr_w { .. } => {
+            return Err(Error::UnsupportedInstruction("jsr"))
+        }
+        Instruction::invokespecial {
+            class,
+            name,
+            descriptor,
+            ..
+        } => {
+            let descriptor = MethodDescriptor::parse(descriptor)?;
+            frame.pop_n(descriptor.parameters.len(), offset)?;
+            let receiver = frame.pop(offset)?;
+
+            if name == "<init>" {
+                let initialized = match receiver {
+                    VerificationType::UninitializedThis => method.class_name.to_string(),
+                    _ => class.clone(),
+                };
+                frame.initialize(&receiver, VerificationType::Object(initialized));
+            }
+
+            push_return(frame, &descriptor)?;
+        }
+        Instruction::invokevirtual { descriptor, .. }
+        | Instruction::invokeinterface { descriptor, .. } => {
+            let descriptor = MethodDescriptor::parse(descriptor)?;
+            frame.pop_n(descriptor.parameters.len() + 1, offset)?;
+            push_return(frame, &descriptor)?;
+        }
+        Instruction::invokestatic { descriptor, .. }
+        | Instruction::invokedynamic { descriptor, .. } => {
+            let descriptor = MethodDescriptor::parse(descriptor)?;
+            frame.pop_n(descriptor.parameters.len(), offset)?;
+            push_return(frame, &descriptor)?;
+        }
+        Instruction::getstatic { descriptor, .. } => {
+            frame.push(VerificationType::from_descriptor(descriptor)?)
+        }
+        Instruction::putstatic { .. } => frame.pop_n(1, offset)?,
+        Instruction::getfield { descriptor, .. } => {
+            frame.pop_n(1, offset)?;
+            frame.push(VerificationType::from_descriptor(descriptor)?);
+        }
+        Instruction::putfield { .. } => frame.pop_n(2, offset)?,
+        Instruction::new { .. } => frame.push(VerificationType::Uninitialized(offset as u16)),
+        Instruction::checkcast { class } => {
+            frame.pop_n(1, offset)?;
+            frame.push(VerificationType::Object(class.clone()));
+        }
+        Instruction::instanceof { .. } | Instruction::arraylength => {
+            frame.pop_n(1, offset)?;
+            frame.push(VerificationType::Integer);
+        }
+        Instruction::newarray { ty } => {
+            frame.pop_n(1, offset)?;
+            frame.push(VerificationType::Object(ty.descriptor().to_string()));
+        }
+        Instruction::anewarray { class } => {
+            frame.pop_n(1, offset)?;
+            let array = if class.starts_with('[') {
+                format!("[{}", class)
+            } else {
+                format!("[L{};", class)
+            };
+            frame.push(VerificationType::Object(array));
+        }
+        Instruction::multianewarray { class, dimensions } => {
+            frame.pop_n(*dimensions as usize, offset)?;
+            frame.push(VerificationType::Object(class.clone()));
+        }
+        Instruction::iaload
+        | Instruction::baload
+        | Instruction::caload
+        | Instruction::saload => {
+            frame.pop_n(2, offset)?;
+            frame.push(VerificationType::Integer);
+        }
+        Instruction::laload => {
+            frame.pop_n(2, offset)?;
+            frame.push(VerificationType::Long);
+        }
+        Instruction::faload => {
+            frame.pop_n(2, offset)?;
+            frame.push(VerificationType::Float);
+        }
+        Instruction::daload => {
+            frame.pop_n(2, offset)?;
+            frame.push(VerificationType::Double);
+        }
+        Instruction::aaload => {
+            frame.pop_n(1, offset)?;
+            let element = match frame.pop(offset)? {
+                VerificationType::Object(array) if array.starts_with('[') => {
+                    VerificationType::from_descriptor(&array[1..])?
+                }
+                VerificationType::Null => VerificationType::Null,
+                _ => VerificationType::Object(consts::OBJECT_CLASS_NAME.to_string()),
+            };
+            frame.push(element);
+        }
+        Instruction::iastore
+        | Instruction::lastore
+        | Instruction::fastore
+        | Instruction::dastore
+        | Instruction::aastore
+        | Instruction::bastore
+        | Instruction::castore
+        | Instruction::sastore => frame.pop_n(3, offset)?,
+        Instruction::nop => {}
+        Instruction::pop => {
+            frame.pop_slots(1, offset)?;
+        }
+        Instruction::pop2 => {
+            frame.pop_slots(2, offset)?;
+        }
+        Instruction::dup => frame.duplicate(1, 0, offset)?,
+        Instruction::dup_x1 => frame.duplicate(1, 1, offset)?,
+        Instruction::dup_x2 => frame.duplicate(1, 2, offset)?,
+        Instruction::dup2 => frame.duplicate(2, 0, offset)?,
+        Instruction::dup2_x1 => frame.duplicate(2, 1, offset)?,
+        Instruction::dup2_x2 => frame.duplicate(2, 2, offset)?,
+        Instruction::swap => {
+            let top = frame.pop_slots(1, offset)?;
+            let below = frame.pop_slots(1, offset)?;
+            frame.stack.extend(top);
+            frame.stack.extend(below);
+        }
+        Instruction::ireturn
+        | Instruction::lreturn
+        | Instruction::freturn
+        | Instruction::dreturn
+        | Instruction::areturn => frame.pop_n(1, offset)?,
+        Instruction::r#return => {}
+        Instruction::athrow | Instruction::monitorenter | Instruction::monitorexit => {
+            frame.pop_n(1, offset)?
+        }
+    }
+
+    Ok(())
+}
+
+fn push_return(frame: &mut Frame, descriptor: &MethodDescriptor) -> Result<(), Error> {
+    if descriptor.return_type != "V" {
+        frame.push(VerificationType::from_descriptor(&descriptor.return_type)?);
+    }
+    Ok(())
+}
@@ -1,6 +1,26 @@
-use crate::{constant_pool::ConstantPool, Error};
+use crate::{
+    annotation::Annotation,
+    constant_pool::{Constant, ConstantPool},
+    Error,
+};
+
+use self::stack_map_table::StackMapFrame;
 
 pub mod code;
+mod frames;
+pub mod stack_map_table;
+pub mod try_builder;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// An entry in the exception table of a `Code` attribute.
+/// Exceptions thrown by code in `start_pc..end_pc` which are instances of `catch_type` jump to
+/// `handler_pc`. A `catch_type` of [None] catches every exception.
+pub struct ExceptionTableEntry {
+    pub start_pc: u16,
+    pub end_pc: u16,
+    pub handler_pc: u16,
+    pub catch_type: Option<String>,
+}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 /// Represents a JVM attribute.
@@ -11,43 +31,142 @@ pub enum Attribute {
         max_stack: u16,
         max_locals: u16,
         code: Vec<u8>,
-        // exception_table: Vec<ExceptionTableEntry>,
+        exception_table: Vec<ExceptionTableEntry>,
         attributes: Vec<Attribute>,
     },
+    StackMapTable {
+        entries: Vec<StackMapFrame>,
+    },
+    /// The bootstrap methods of a class, as the index of a method handle and the indices of its
+    /// static arguments. See [ConstantPool::bootstrap_methods].
+    BootstrapMethods {
+        methods: Vec<(u16, Vec<u16>)>,
+    },
+    /// The initial value of a static field.
+    ConstantValue {
+        value: Constant,
+    },
+    /// The generic signature of a class, field or method, such as `Ljava/util/List<TT;>;`.
+    Signature {
+        signature: String,
+    },
+    RuntimeVisibleAnnotations {
+        annotations: Vec<Annotation>,
+    },
+    RuntimeInvisibleAnnotations {
+        annotations: Vec<Annotation>,
+    },
 }
 
 impl Attribute {
     /// Emit the attribute as a byte vector.
     pub fn emit(self, constant_pool: &mut ConstantPool) -> Result<Vec<u8>, Error> {
         let mut bytes = Vec::new();
+        let visible = matches!(self, Attribute::RuntimeVisibleAnnotations { .. });
 
         match self {
             Attribute::Code {
                 max_stack,
                 max_locals,
                 code,
-                // exception_table,
+                exception_table,
                 attributes,
             } => {
                 let name_index = constant_pool.insert_string("Code".to_string());
                 bytes.extend_from_slice(&name_index.to_be_bytes());
-                let attribute_length: u32 = (12 + code.len() + attributes.len()) as u32;
-                bytes.extend_from_slice(&attribute_length.to_be_bytes());
-                bytes.extend_from_slice(&max_stack.to_be_bytes());
-                bytes.extend_from_slice(&max_locals.to_be_bytes());
+
+                let mut body = Vec::new();
+                body.extend_from_slice(&max_stack.to_be_bytes());
+                body.extend_from_slice(&max_locals.to_be_bytes());
 
                 let code_length = code.len() as u32;
-                bytes.extend_from_slice(&code_length.to_be_bytes());
+                body.extend_from_slice(&code_length.to_be_bytes());
+
+                body.extend_from_slice(&code);
+
+                body.extend_from_slice(&(exception_table.len() as u16).to_be_bytes());
+
+                for entry in exception_table {
+                    body.extend_from_slice(&entry.start_pc.to_be_bytes());
+                    body.extend_from_slice(&entry.end_pc.to_be_bytes());
+                    body.extend_from_slice(&entry.handler_pc.to_be_bytes());
+                    let catch_type = entry
+                        .catch_type
+                        .map_or(0, |catch_type| constant_pool.insert_class(catch_type));
+                    body.extend_from_slice(&catch_type.to_be_bytes());
+                }
+
+                body.extend_from_slice(&(attributes.len() as u16).to_be_bytes());
+
+                for attribute in attributes {
+                    body.extend::<Vec<u8>>(attribute.emit(constant_pool)?);
+                }
+
+                bytes.extend_from_slice(&(body.len() as u32).to_be_bytes());
+                bytes.extend(body);
+            }
+            Attribute::StackMapTable { entries } => {
+                let name_index = constant_pool.insert_string("StackMapTable".to_string());
+                bytes.extend_from_slice(&name_index.to_be_bytes());
+
+                let mut body = Vec::new();
+                body.extend_from_slice(&(entries.len() as u16).to_be_bytes());
+
+                for entry in entries {
+                    body.extend(entry.emit(constant_pool));
+                }
+
+                bytes.extend_from_slice(&(body.len() as u32).to_be_bytes());
+                bytes.extend(body);
+            }
+            Attribute::BootstrapMethods { methods } => {
+                let name_index = constant_pool.insert_string("BootstrapMethods".to_string());
+                bytes.extend_from_slice(&name_index.to_be_bytes());
+
+                let mut body = Vec::new();
+                body.extend_from_slice(&(methods.len() as u16).to_be_bytes());
+
+                for (handle, arguments) in methods {
+                    body.extend_from_slice(&handle.to_be_bytes());
+                    body.extend_from_slice(&(arguments.len() as u16).to_be_bytes());
+                    for argument in arguments {
+                        body.extend_from_slice(&argument.to_be_bytes());
+                    }
+                }
+
+                bytes.extend_from_slice(&(body.len() as u32).to_be_bytes());
+                bytes.extend(body);
+            }
+            Attribute::ConstantValue { value } => {
+                let name_index = constant_pool.insert_string("ConstantValue".to_string());
+                bytes.extend_from_slice(&name_index.to_be_bytes());
+                bytes.extend_from_slice(&2u32.to_be_bytes());
+                bytes.extend_from_slice(&constant_pool.insert_constant(&value).to_be_bytes());
+            }
+            Attribute::Signature { signature } => {
+                let name_index = constant_pool.insert_string("Signature".to_string());
+                bytes.extend_from_slice(&name_index.to_be_bytes());
+                bytes.extend_from_slice(&2u32.to_be_bytes());
+                bytes.extend_from_slice(&constant_pool.insert_string(signature).to_be_bytes());
+            }
+            Attribute::RuntimeVisibleAnnotations { annotations }
+            | Attribute::RuntimeInvisibleAnnotations { annotations } => {
+                let name = match visible {
+                    true => "RuntimeVisibleAnnotations",
+                    false => "RuntimeInvisibleAnnotations",
+                };
+                let name_index = constant_pool.insert_string(name.to_string());
+                bytes.extend_from_slice(&name_index.to_be_bytes());
 
-                bytes.extend_from_slice(&code);
+                let mut body = Vec::new();
+                body.extend_from_slice(&(annotations.len() as u16).to_be_bytes());
 
-                bytes.extend_from_slice(&[0x00, 0x00]);
-                bytes.extend_from_slice(&[0x00, 0x00]);
-                // bytes.extend_from_slice(&attributes.len().to_be_bytes());
+                for annotation in annotations {
+                    body.extend(annotation.emit(constant_pool));
+                }
 
-                // for attribute in attributes {
-                // bytes.extend::<Vec<u8>>(attribute.emit(constant_pool));
-                // }
+                bytes.extend_from_slice(&(body.len() as u32).to_be_bytes());
+                bytes.extend(body);
             }
         }
 
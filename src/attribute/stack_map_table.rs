@@ -0,0 +1,166 @@
+use crate::{constant_pool::ConstantPool, descriptor::validate_field_type, Error};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// The type of a local variable or operand stack entry, as seen by the verifier.
+/// See https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.7.4
+pub enum VerificationType {
+    Top,
+    Integer,
+    Float,
+    Long,
+    Double,
+    Null,
+    UninitializedThis,
+    /// A class, in the internal format (`java/lang/String`), or an array descriptor (`[I`).
+    Object(String),
+    /// An object created by the `new` instruction at the given offset, which has not yet been initialized.
+    Uninitialized(u16),
+}
+
+impl VerificationType {
+    /// Returns the verification type of a value with the given field descriptor, or
+    /// [Error::InvalidDescriptor] if it is not a valid field type.
+    /// `boolean`, `byte`, `char` and `short` are all represented as [VerificationType::Integer].
+    pub fn from_descriptor(descriptor: &str) -> Result<Self, Error> {
+        validate_field_type(descriptor)?;
+        Ok(match descriptor.as_bytes()[0] {
+            b'B' | b'C' | b'I' | b'S' | b'Z' => VerificationType::Integer,
+            b'F' => VerificationType::Float,
+            b'J' => VerificationType::Long,
+            b'D' => VerificationType::Double,
+            b'L' => VerificationType::Object(descriptor[1..descriptor.len() - 1].to_string()),
+            _ => VerificationType::Object(descriptor.to_string()),
+        })
+    }
+
+    /// Returns whether this type takes two slots.
+    pub fn is_wide(&self) -> bool {
+        matches!(self, VerificationType::Long | VerificationType::Double)
+    }
+
+    /// Returns whether this type is a reference (including `null` and uninitialized objects).
+    pub fn is_reference(&self) -> bool {
+        matches!(
+            self,
+            VerificationType::Null
+                | VerificationType::UninitializedThis
+                | VerificationType::Object(_)
+                | VerificationType::Uninitialized(_)
+        )
+    }
+
+    /// Emit the verification type as a byte vector.
+    pub fn emit(self, constant_pool: &mut ConstantPool) -> Vec<u8> {
+        match self {
+            VerificationType::Top => vec![0],
+            VerificationType::Integer => vec![1],
+            VerificationType::Float => vec![2],
+            VerificationType::Double => vec![3],
+            VerificationType::Long => vec![4],
+            VerificationType::Null => vec![5],
+            VerificationType::UninitializedThis => vec![6],
+            VerificationType::Object(class) => {
+                let mut bytes = vec![7];
+                bytes.extend_from_slice(&constant_pool.insert_class(class).to_be_bytes());
+                bytes
+            }
+            VerificationType::Uninitialized(offset) => {
+                let mut bytes = vec![8];
+                bytes.extend_from_slice(&offset.to_be_bytes());
+                bytes
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// An entry in the `StackMapTable` attribute.
+/// Each frame is relative to the previous one, starting from the implicit frame built from the
+/// method descriptor. The extended forms are chosen automatically when emitting.
+/// See https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.7.4
+pub enum StackMapFrame {
+    Same {
+        offset_delta: u16,
+    },
+    SameLocals1StackItem {
+        offset_delta: u16,
+        stack: VerificationType,
+    },
+    Chop {
+        offset_delta: u16,
+        count: u8,
+    },
+    Append {
+        offset_delta: u16,
+        locals: Vec<VerificationType>,
+    },
+    Full {
+        offset_delta: u16,
+        locals: Vec<VerificationType>,
+        stack: Vec<VerificationType>,
+    },
+}
+
+impl StackMapFrame {
+    /// Emit the frame as a byte vector.
+    pub fn emit(self, constant_pool: &mut ConstantPool) -> Vec<u8> {
+        let mut bytes = Vec::new();
+
+        match self {
+            StackMapFrame::Same { offset_delta } if offset_delta < 64 => {
+                bytes.push(offset_delta as u8);
+            }
+            StackMapFrame::Same { offset_delta } => {
+                bytes.push(251);
+                bytes.extend_from_slice(&offset_delta.to_be_bytes());
+            }
+            StackMapFrame::SameLocals1StackItem {
+                offset_delta,
+                stack,
+            } => {
+                if offset_delta < 64 {
+                    bytes.push(64 + offset_delta as u8);
+                } else {
+                    bytes.push(247);
+                    bytes.extend_from_slice(&offset_delta.to_be_bytes());
+                }
+                bytes.extend(stack.emit(constant_pool));
+            }
+            StackMapFrame::Chop {
+                offset_delta,
+                count,
+            } => {
+                bytes.push(251 - count);
+                bytes.extend_from_slice(&offset_delta.to_be_bytes());
+            }
+            StackMapFrame::Append {
+                offset_delta,
+                locals,
+            } => {
+                bytes.push(251 + locals.len() as u8);
+                bytes.extend_from_slice(&offset_delta.to_be_bytes());
+                for local in locals {
+                    bytes.extend(local.emit(constant_pool));
+                }
+            }
+            StackMapFrame::Full {
+                offset_delta,
+                locals,
+                stack,
+            } => {
+                bytes.push(255);
+                bytes.extend_from_slice(&offset_delta.to_be_bytes());
+                bytes.extend_from_slice(&(locals.len() as u16).to_be_bytes());
+                for local in locals {
+                    bytes.extend(local.emit(constant_pool));
+                }
+                bytes.extend_from_slice(&(stack.len() as u16).to_be_bytes());
+                for item in stack {
+                    bytes.extend(item.emit(constant_pool));
+                }
+            }
+        }
+
+        bytes
+    }
+}
@@ -0,0 +1,341 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    instruction::{
+        aload, astore, athrow, dload, dstore, fload, fstore, goto, iload, istore, label, lload,
+        lstore, Instruction,
+    },
+    label::Label,
+};
+
+use super::code::CodeBuilder;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A builder for a structured `try`/`catch`/`finally` block, added to a [CodeBuilder] with
+/// [CodeBuilder::try_block].
+///
+/// This generates the same layout as `javac`: the `finally` block is duplicated onto every path
+/// that leaves the `try` or a `catch` (falling off the end, returning, or branching to a label
+/// outside the block), and a handler that catches every exception runs the `finally` block and
+/// rethrows. The exception or return value being propagated is stored in a new local while a copy
+/// of the `finally` block runs, so each copy starts with an empty operand stack. Each copy gets
+/// fresh copies of the labels placed in it.
+///
+/// A `try` block can be nested in the body or a `catch` clause of another by lowering it with
+/// [TryBuilder::lower] first. The `finally` block itself cannot contain a nested `try` block, as
+/// its copies would not be protected by the nested block's handlers.
+pub struct TryBuilder {
+    body: Vec<Instruction>,
+    catches: Vec<(String, Vec<Instruction>)>,
+    finally: Option<Vec<Instruction>>,
+}
+
+impl TryBuilder {
+    /// Creates a new [TryBuilder].
+    pub fn new() -> Self {
+        Self {
+            body: Vec::new(),
+            catches: Vec::new(),
+            finally: None,
+        }
+    }
+
+    /// Extends the instructions of the `try` block.
+    pub fn body<I>(mut self, instructions: I) -> Self
+    where
+        I: IntoIterator<Item = Instruction>,
+    {
+        self.body.extend(instructions);
+        self
+    }
+
+    /// Adds a `catch` clause for exceptions of the class `catch_type`.
+    /// The caught exception is on top of the operand stack when the clause starts.
+    /// Clauses are tried in the order they are added.
+    pub fn catch<I>(mut self, catch_type: impl Into<String>, instructions: I) -> Self
+    where
+        I: IntoIterator<Item = Instruction>,
+    {
+        self.catches
+            .push((catch_type.into(), instructions.into_iter().collect()));
+        self
+    }
+
+    /// Sets the `finally` block, which runs however the `try` and `catch` blocks are left.
+    pub fn finally<I>(mut self, instructions: I) -> Self
+    where
+        I: IntoIterator<Item = Instruction>,
+    {
+        self.finally = Some(instructions.into_iter().collect());
+        self
+    }
+
+    /// Lowers the block into plain instructions, adding its exception handlers to `code`.
+    /// This is used to nest a `try` block inside the body or a `catch` clause of another, which
+    /// must be added to `code` afterwards so that the nested handlers are tried first.
+    pub fn lower(self, code: &mut CodeBuilder) -> Vec<Instruction> {
+        let finally = self.finally.as_deref();
+        let after = code.new_label();
+        let mut instructions = Vec::new();
+
+        let mut body_ranges = Vec::new();
+        let mut body = inline_finally(
+            code,
+            self.body,
+            finally,
+            Inlining::Finally,
+            &mut body_ranges,
+        );
+        exit_to(code, &mut body, finally, after);
+        instructions.extend(body);
+
+        let mut any_ranges = body_ranges.clone();
+        let mut handlers = Vec::new();
+        for (catch_type, body) in self.catches {
+            let handler = code.new_label();
+            handlers.push((handler, catch_type));
+
+            let mut body = inline_finally(code, body, finally, Inlining::Finally, &mut any_ranges);
+            exit_to(code, &mut body, finally, after);
+            instructions.push(label(handler));
+            instructions.extend(body);
+        }
+
+        for (handler, catch_type) in handlers {
+            for (start, end) in body_ranges.iter() {
+                code.add_try_catch(*start, *end, handler, Some(catch_type.clone()));
+            }
+        }
+
+        if let Some(finally) = finally {
+            let handler = code.new_label();
+            let exception = code.new_local("Ljava/lang/Throwable;");
+            instructions.extend([label(handler), astore(exception)]);
+            instructions.extend(copy_block(code, finally));
+            instructions.extend([aload(exception), athrow()]);
+
+            for (start, end) in any_ranges {
+                code.add_try_catch(start, end, handler, None);
+            }
+        }
+
+        instructions.push(label(after));
+        instructions
+    }
+}
+
+impl Default for TryBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Ends a lowered `try` or `catch` block by running the `finally` block and jumping past the
+/// whole construct, if execution can reach the end of the block.
+pub(crate) fn exit_to(
+    code: &mut CodeBuilder,
+    block: &mut Vec<Instruction>,
+    finally: Option<&[Instruction]>,
+    after: Label,
+) {
+    // The end is reachable if the last instruction falls through, or a label after it is branched
+    // to, such as the end of a nested `try` block.
+    let last = block
+        .iter()
+        .rposition(|instruction| !matches!(instruction, Instruction::label { .. }));
+    let falls_through = match last {
+        Some(last) => {
+            block[last].falls_through()
+                || block[last + 1..]
+                    .iter()
+                    .any(|instruction| match instruction {
+                        Instruction::label { label } => block
+                            .iter()
+                            .any(|instruction| instruction.branch_targets().contains(label)),
+                        _ => false,
+                    })
+        }
+        None => true,
+    };
+
+    if falls_through {
+        if let Some(finally) = finally {
+            block.extend(copy_block(code, finally));
+        }
+        block.push(goto(after));
+    }
+}
+
+/// Copies `block`, replacing every label placed in it with a new one, so that the copy can be
+/// placed alongside the original. Branches to labels outside `block` are left as they are.
+fn copy_block(code: &mut CodeBuilder, block: &[Instruction]) -> Vec<Instruction> {
+    let labels: HashMap<Label, Label> = block
+        .iter()
+        .filter_map(|instruction| match instruction {
+            Instruction::label { label } => Some((*label, code.new_label())),
+            _ => None,
+        })
+        .collect();
+
+    block
+        .iter()
+        .cloned()
+        .map(|mut instruction| {
+            for label in instruction.labels_mut() {
+                if let Some(copy) = labels.get(label) {
+                    *label = *copy;
+                }
+            }
+            instruction
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How [inline_finally] copies a `finally` block before each exit.
+pub(crate) enum Inlining {
+    /// The layout `javac` uses for `finally`: a value being returned is stored in a local while the
+    /// copy runs, and the copies are not protected by the block's handlers.
+    Finally,
+    /// The layout `javac` uses for `synchronized`: the copies run with the value being returned
+    /// still on the operand stack, and are protected by the block's handlers.
+    Synchronized,
+}
+
+/// Appends a copy of `finally` to `instructions`, placing `end` before the copy, or after it for
+/// [Inlining::Synchronized].
+fn copy_finally(
+    code: &mut CodeBuilder,
+    instructions: &mut Vec<Instruction>,
+    finally: &[Instruction],
+    inlining: Inlining,
+    end: Label,
+) {
+    if inlining == Inlining::Finally {
+        instructions.push(label(end));
+    }
+    instructions.extend(copy_block(code, finally));
+    if inlining == Inlining::Synchronized {
+        instructions.push(label(end));
+    }
+}
+
+/// Returns instructions that store the value returned by `instruction` in a new local, and load it
+/// back, or [None] if it does not return a value.
+fn save_return_value(
+    code: &mut CodeBuilder,
+    instruction: &Instruction,
+) -> Option<(Instruction, Instruction)> {
+    let (descriptor, store, load): (_, fn(_) -> _, fn(_) -> _) = match instruction {
+        Instruction::ireturn => ("I", istore, iload),
+        Instruction::lreturn => ("J", lstore, lload),
+        Instruction::freturn => ("F", fstore, fload),
+        Instruction::dreturn => ("D", dstore, dload),
+        Instruction::areturn => ("Ljava/lang/Object;", astore, aload),
+        _ => return None,
+    };
+    let local = code.new_local(descriptor);
+    Some((store(local), load(local)))
+}
+
+/// Copies the `finally` block before every instruction in `body` that leaves it, other than by
+/// throwing. The protected ranges of `body` are appended to `ranges`. See [Inlining] for how the
+/// copies are laid out.
+pub(crate) fn inline_finally(
+    code: &mut CodeBuilder,
+    body: Vec<Instruction>,
+    finally: Option<&[Instruction]>,
+    inlining: Inlining,
+    ranges: &mut Vec<(Label, Label)>,
+) -> Vec<Instruction> {
+    let internal: HashSet<Label> = body
+        .iter()
+        .filter_map(|instruction| match instruction {
+            Instruction::label { label } => Some(*label),
+            _ => None,
+        })
+        .collect();
+
+    let mut instructions = Vec::new();
+    let mut start = code.new_label();
+    instructions.push(label(start));
+
+    for instruction in body {
+        let exits = match instruction.branch_targets().as_slice() {
+            [] => instruction.is_return(),
+            targets => targets.iter().any(|target| !internal.contains(target)),
+        };
+
+        let finally = match finally {
+            Some(finally) if exits => finally,
+            _ => {
+                instructions.push(instruction);
+                continue;
+            }
+        };
+
+        let end = code.new_label();
+        let next = code.new_label();
+
+        if let Instruction::tableswitch { .. } | Instruction::lookupswitch { .. } = instruction {
+            // Each target outside the block gets its own copy of the `finally` block.
+            let mut instruction = instruction;
+            let mut trampolines = Vec::new();
+            for target in instruction.labels_mut() {
+                if !internal.contains(target) {
+                    let trampoline = code.new_label();
+                    trampolines.push((trampoline, *target));
+                    *target = trampoline;
+                }
+            }
+
+            instructions.push(instruction);
+            if inlining == Inlining::Finally {
+                instructions.push(label(end));
+            }
+            for (trampoline, target) in trampolines {
+                instructions.push(label(trampoline));
+                instructions.extend(copy_block(code, finally));
+                instructions.push(goto(target));
+            }
+            if inlining == Inlining::Synchronized {
+                instructions.push(label(end));
+            }
+        } else {
+            match instruction
+                .branch_target()
+                .map(|target| (instruction.inverted(next), target))
+            {
+                Some((Some(inverted), target)) => {
+                    instructions.push(inverted);
+                    copy_finally(code, &mut instructions, finally, inlining, end);
+                    instructions.push(goto(target));
+                }
+                _ => {
+                    let saved = match inlining {
+                        Inlining::Finally => save_return_value(code, &instruction),
+                        Inlining::Synchronized => None,
+                    };
+                    match saved {
+                        Some((store, load)) => {
+                            instructions.push(store);
+                            copy_finally(code, &mut instructions, finally, inlining, end);
+                            instructions.push(load);
+                        }
+                        None => copy_finally(code, &mut instructions, finally, inlining, end),
+                    }
+                    instructions.push(instruction);
+                }
+            }
+        }
+
+        ranges.push((start, end));
+        start = next;
+        instructions.push(label(start));
+    }
+
+    let end = code.new_label();
+    instructions.push(label(end));
+    ranges.push((start, end));
+    instructions
+}
@@ -0,0 +1,400 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::{consts, Error};
+
+/// Provides information about classes that jaby does not generate itself.
+/// This is used when computing `StackMapTable` frames, where two reference types must be merged
+/// into their common super class.
+pub trait ClassHierarchy {
+    /// Returns the super class of `class`, in the internal format (`java/lang/Object`).
+    /// Returns [None] for `java/lang/Object`, and for classes that are not known.
+    fn super_class_of(&self, class: &str) -> Option<String>;
+
+    /// Returns whether `class` is an interface.
+    fn is_interface(&self, class: &str) -> bool;
+
+    /// Returns the most specific common super class of `a` and `b`.
+    /// Interfaces have `java/lang/Object` as their common super class, as the verifier treats
+    /// interface types as `java/lang/Object`. Returns [Error::UnknownClass] if the answer depends
+    /// on the super class of a class that is not known.
+    fn common_super_class(&self, a: &str, b: &str) -> Result<String, Error> {
+        if a == b {
+            return Ok(a.to_string());
+        }
+
+        if self.is_interface(a) || self.is_interface(b) {
+            return Ok(consts::OBJECT_CLASS_NAME.to_string());
+        }
+
+        let mut ancestors = vec![a.to_string()];
+        while let Some(super_class) = self.super_class_of(ancestors.last().unwrap()) {
+            if ancestors.contains(&super_class) {
+                break;
+            }
+            ancestors.push(super_class);
+        }
+
+        let mut class = b.to_string();
+        loop {
+            if ancestors.contains(&class) {
+                return Ok(class);
+            }
+
+            match self.super_class_of(&class) {
+                Some(super_class) if super_class != class => class = super_class,
+                _ if class != consts::OBJECT_CLASS_NAME => return Err(Error::UnknownClass(class)),
+                _ => return Err(Error::UnknownClass(ancestors.pop().unwrap())),
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ClassInfo {
+    super_class: Option<String>,
+    is_interface: bool,
+}
+
+#[derive(Debug, Clone)]
+/// A [ClassHierarchy] backed by a map of known classes.
+/// [DefaultClassHierarchy::new] knows `java/lang/Object` and the core `java.lang` hierarchy. More
+/// classes can be added by hand, which allows classes that do not exist yet to reference each other,
+/// or loaded from existing class files in a directory or jar.
+pub struct DefaultClassHierarchy {
+    classes: HashMap<String, ClassInfo>,
+}
+
+/// Classes in `java.lang` and their super classes.
+const JAVA_LANG_CLASSES: &[(&str, &str)] = &[
+    ("java/lang/String", "java/lang/Object"),
+    ("java/lang/Class", "java/lang/Object"),
+    ("java/lang/System", "java/lang/Object"),
+    ("java/lang/Math", "java/lang/Object"),
+    ("java/lang/Thread", "java/lang/Object"),
+    ("java/lang/Enum", "java/lang/Object"),
+    ("java/lang/Record", "java/lang/Object"),
+    ("java/lang/Boolean", "java/lang/Object"),
+    ("java/lang/Character", "java/lang/Object"),
+    ("java/lang/Number", "java/lang/Object"),
+    ("java/lang/Byte", "java/lang/Number"),
+    ("java/lang/Short", "java/lang/Number"),
+    ("java/lang/Integer", "java/lang/Number"),
+    ("java/lang/Long", "java/lang/Number"),
+    ("java/lang/Float", "java/lang/Number"),
+    ("java/lang/Double", "java/lang/Number"),
+    ("java/lang/AbstractStringBuilder", "java/lang/Object"),
+    ("java/lang/StringBuilder", "java/lang/AbstractStringBuilder"),
+    ("java/lang/StringBuffer", "java/lang/AbstractStringBuilder"),
+    ("java/lang/Throwable", "java/lang/Object"),
+    ("java/lang/Exception", "java/lang/Throwable"),
+    ("java/lang/Error", "java/lang/Throwable"),
+    ("java/lang/RuntimeException", "java/lang/Exception"),
+    (
+        "java/lang/ReflectiveOperationException",
+        "java/lang/Exception",
+    ),
+    (
+        "java/lang/ClassNotFoundException",
+        "java/lang/ReflectiveOperationException",
+    ),
+    ("java/lang/InterruptedException", "java/lang/Exception"),
+    (
+        "java/lang/CloneNotSupportedException",
+        "java/lang/Exception",
+    ),
+    (
+        "java/lang/ArithmeticException",
+        "java/lang/RuntimeException",
+    ),
+    (
+        "java/lang/ArrayStoreException",
+        "java/lang/RuntimeException",
+    ),
+    ("java/lang/ClassCastException", "java/lang/RuntimeException"),
+    (
+        "java/lang/IllegalArgumentException",
+        "java/lang/RuntimeException",
+    ),
+    (
+        "java/lang/NumberFormatException",
+        "java/lang/IllegalArgumentException",
+    ),
+    (
+        "java/lang/IllegalStateException",
+        "java/lang/RuntimeException",
+    ),
+    (
+        "java/lang/IllegalMonitorStateException",
+        "java/lang/RuntimeException",
+    ),
+    (
+        "java/lang/IndexOutOfBoundsException",
+        "java/lang/RuntimeException",
+    ),
+    (
+        "java/lang/ArrayIndexOutOfBoundsException",
+        "java/lang/IndexOutOfBoundsException",
+    ),
+    (
+        "java/lang/StringIndexOutOfBoundsException",
+        "java/lang/IndexOutOfBoundsException",
+    ),
+    (
+        "java/lang/NegativeArraySizeException",
+        "java/lang/RuntimeException",
+    ),
+    (
+        "java/lang/NullPointerException",
+        "java/lang/RuntimeException",
+    ),
+    ("java/lang/SecurityException", "java/lang/RuntimeException"),
+    (
+        "java/lang/UnsupportedOperationException",
+        "java/lang/RuntimeException",
+    ),
+    ("java/lang/AssertionError", "java/lang/Error"),
+    ("java/lang/LinkageError", "java/lang/Error"),
+    ("java/lang/VirtualMachineError", "java/lang/Error"),
+    (
+        "java/lang/OutOfMemoryError",
+        "java/lang/VirtualMachineError",
+    ),
+    (
+        "java/lang/StackOverflowError",
+        "java/lang/VirtualMachineError",
+    ),
+];
+
+/// Interfaces in `java.lang`.
+const JAVA_LANG_INTERFACES: &[&str] = &[
+    "java/lang/Runnable",
+    "java/lang/Comparable",
+    "java/lang/CharSequence",
+    "java/lang/Iterable",
+    "java/lang/AutoCloseable",
+    "java/lang/Cloneable",
+    "java/lang/Appendable",
+    "java/lang/Readable",
+];
+
+/// The `ACC_INTERFACE` class access flag.
+const ACC_INTERFACE: u16 = 0x0200;
+
+impl DefaultClassHierarchy {
+    /// Creates a new [DefaultClassHierarchy] that knows the core `java.lang` classes.
+    pub fn new() -> Self {
+        let mut hierarchy = Self::empty();
+
+        hierarchy.classes.insert(
+            consts::OBJECT_CLASS_NAME.to_string(),
+            ClassInfo {
+                super_class: None,
+                is_interface: false,
+            },
+        );
+
+        for (class, super_class) in JAVA_LANG_CLASSES {
+            hierarchy = hierarchy.class(*class, *super_class);
+        }
+
+        for interface in JAVA_LANG_INTERFACES {
+            hierarchy = hierarchy.interface(*interface);
+        }
+
+        hierarchy
+    }
+
+    /// Creates a new [DefaultClassHierarchy] that knows no classes.
+    pub fn empty() -> Self {
+        Self {
+            classes: HashMap::new(),
+        }
+    }
+
+    /// Adds a class and its super class, both in the internal format (`com/example/ExampleClass`).
+    pub fn class(mut self, class: impl Into<String>, super_class: impl Into<String>) -> Self {
+        self.classes.insert(
+            class.into(),
+            ClassInfo {
+                super_class: Some(super_class.into()),
+                is_interface: false,
+            },
+        );
+        self
+    }
+
+    /// Adds an interface, in the internal format (`com/example/ExampleInterface`).
+    pub fn interface(mut self, interface: impl Into<String>) -> Self {
+        self.classes.insert(
+            interface.into(),
+            ClassInfo {
+                super_class: Some(consts::OBJECT_CLASS_NAME.to_string()),
+                is_interface: true,
+            },
+        );
+        self
+    }
+
+    /// Adds the class defined by the contents of a `.class` file.
+    pub fn class_file(mut self, bytes: &[u8]) -> Result<Self, Error> {
+        let (class, info) = parse_class_file(bytes)?;
+        self.classes.insert(class, info);
+        Ok(self)
+    }
+
+    /// Adds every `.class` file in a directory and its subdirectories.
+    pub fn directory(mut self, path: impl AsRef<Path>) -> Result<Self, Error> {
+        for entry in fs::read_dir(path)? {
+            let path = entry?.path();
+
+            if path.is_dir() {
+                self = self.directory(&path)?;
+            } else if path
+                .extension()
+                .is_some_and(|extension| extension == "class")
+            {
+                self = self.class_file(&fs::read(&path)?)?;
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Adds every `.class` file in a jar. Requires the `jar` feature.
+    #[cfg(feature = "jar")]
+    pub fn jar(mut self, path: impl AsRef<Path>) -> Result<Self, Error> {
+        use std::io::Read;
+
+        let mut archive =
+            zip::ZipArchive::new(fs::File::open(path)?).map_err(|_| Error::InvalidClassFile)?;
+
+        for index in 0..archive.len() {
+            let mut file = archive
+                .by_index(index)
+                .map_err(|_| Error::InvalidClassFile)?;
+
+            if file.is_file() && file.name().ends_with(".class") {
+                let mut bytes = Vec::new();
+                file.read_to_end(&mut bytes)?;
+                self = self.class_file(&bytes)?;
+            }
+        }
+
+        Ok(self)
+    }
+}
+
+impl ClassHierarchy for DefaultClassHierarchy {
+    fn super_class_of(&self, class: &str) -> Option<String> {
+        self.classes.get(class)?.super_class.clone()
+    }
+
+    fn is_interface(&self, class: &str) -> bool {
+        self.classes
+            .get(class)
+            .is_some_and(|info| info.is_interface)
+    }
+}
+
+impl Default for DefaultClassHierarchy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A [ClassHierarchy] that knows about the class currently being generated, and defers to another
+/// hierarchy for everything else.
+pub(crate) struct WithClass<'a> {
+    pub inner: &'a dyn ClassHierarchy,
+    pub class: &'a str,
+    pub super_class: &'a str,
+}
+
+impl ClassHierarchy for WithClass<'_> {
+    fn super_class_of(&self, class: &str) -> Option<String> {
+        if class == self.class {
+            Some(self.super_class.to_string())
+        } else {
+            self.inner.super_class_of(class)
+        }
+    }
+
+    fn is_interface(&self, class: &str) -> bool {
+        class != self.class && self.inner.is_interface(class)
+    }
+}
+
+/// Reads the name, super class and interface flag of a class from the contents of a `.class` file.
+fn parse_class_file(bytes: &[u8]) -> Result<(String, ClassInfo), Error> {
+    let mut position = 0;
+    let mut read = |length: usize| -> Result<&[u8], Error> {
+        let slice = bytes
+            .get(position..position + length)
+            .ok_or(Error::InvalidClassFile)?;
+        position += length;
+        Ok(slice)
+    };
+    let to_u16 = |slice: &[u8]| u16::from_be_bytes([slice[0], slice[1]]);
+
+    if read(4)? != consts::MAGIC.to_be_bytes() {
+        return Err(Error::InvalidClassFile);
+    }
+    read(4)?;
+
+    let constant_pool_count = to_u16(read(2)?);
+    let mut strings = HashMap::new();
+    let mut classes = HashMap::new();
+
+    let mut index = 1;
+    while index < constant_pool_count {
+        let tag = read(1)?[0];
+        match tag {
+            0x01 => {
+                let length = to_u16(read(2)?) as usize;
+                let string = String::from_utf8_lossy(read(length)?).into_owned();
+                strings.insert(index, string);
+            }
+            0x07 => {
+                classes.insert(index, to_u16(read(2)?));
+            }
+            0x08 | 0x10 | 0x13 | 0x14 => {
+                read(2)?;
+            }
+            0x0f => {
+                read(3)?;
+            }
+            0x03 | 0x04 | 0x09 | 0x0a | 0x0b | 0x0c | 0x11 | 0x12 => {
+                read(4)?;
+            }
+            0x05 | 0x06 => {
+                read(8)?;
+                index += 1;
+            }
+            _ => return Err(Error::InvalidClassFile),
+        }
+        index += 1;
+    }
+
+    let access_flags = to_u16(read(2)?);
+    let this_class = to_u16(read(2)?);
+    let super_class = to_u16(read(2)?);
+
+    let class_name = |index: u16| -> Result<String, Error> {
+        classes
+            .get(&index)
+            .and_then(|name_index| strings.get(name_index))
+            .cloned()
+            .ok_or(Error::InvalidClassFile)
+    };
+
+    Ok((
+        class_name(this_class)?,
+        ClassInfo {
+            super_class: match super_class {
+                0 => None,
+                index => Some(class_name(index)?),
+            },
+            is_interface: access_flags & ACC_INTERFACE != 0,
+        },
+    ))
+}
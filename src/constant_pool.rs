@@ -17,6 +17,7 @@ pub enum ConstantPoolEntry {
     Method(u16, u16),
     InterfaceMethod(u16, u16),
     NameAndType(u16, u16),
+    MethodHandle(u8, u16),
     MethodType(u16),
     Dynamic(u16, u16),
     InvokeDynamic(u16, u16),
@@ -24,16 +25,38 @@ pub enum ConstantPoolEntry {
     Package(u16),
 }
 
+/// Encodes a string in the JVM's modified UTF-8, where `\0` takes two bytes and characters outside
+/// the Basic Multilingual Plane are stored as a surrogate pair of three bytes each.
+/// See https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.4.7
+fn modified_utf8(s: &str) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for unit in s.encode_utf16() {
+        match unit {
+            0x0001..=0x007f => bytes.push(unit as u8),
+            0x0000 | 0x0080..=0x07ff => {
+                bytes.push(0xc0 | (unit >> 6) as u8);
+                bytes.push(0x80 | (unit & 0x3f) as u8);
+            }
+            _ => {
+                bytes.push(0xe0 | (unit >> 12) as u8);
+                bytes.push(0x80 | ((unit >> 6) & 0x3f) as u8);
+                bytes.push(0x80 | (unit & 0x3f) as u8);
+            }
+        }
+    }
+    bytes
+}
+
 impl From<ConstantPoolEntry> for Vec<u8> {
     fn from(val: ConstantPoolEntry) -> Vec<u8> {
         let mut bytes = Vec::new();
 
         match val {
             ConstantPoolEntry::String(s) => {
+                let encoded = modified_utf8(&s);
                 bytes.extend_from_slice(&[0x01]);
-                let len: u16 = s.len().try_into().unwrap();
-                bytes.extend(len.to_be_bytes());
-                bytes.extend_from_slice(s.as_bytes());
+                bytes.extend((encoded.len() as u16).to_be_bytes());
+                bytes.extend(encoded);
             }
             ConstantPoolEntry::Integer(i) => {
                 bytes.extend_from_slice(&[0x03]);
@@ -79,26 +102,30 @@ impl From<ConstantPoolEntry> for Vec<u8> {
                 bytes.extend_from_slice(&name.to_be_bytes());
                 bytes.extend_from_slice(&descriptor.to_be_bytes());
             }
+            ConstantPoolEntry::MethodHandle(kind, reference) => {
+                bytes.extend_from_slice(&[0x0f, kind]);
+                bytes.extend_from_slice(&reference.to_be_bytes());
+            }
             ConstantPoolEntry::MethodType(descriptor) => {
                 bytes.extend_from_slice(&[0x10]);
                 bytes.extend_from_slice(&descriptor.to_be_bytes());
             }
             ConstantPoolEntry::Dynamic(bootstrap_method, name_and_type) => {
-                bytes.extend_from_slice(&[0x12]);
+                bytes.extend_from_slice(&[0x11]);
                 bytes.extend_from_slice(&bootstrap_method.to_be_bytes());
                 bytes.extend_from_slice(&name_and_type.to_be_bytes());
             }
             ConstantPoolEntry::InvokeDynamic(bootstrap_method, name_and_type) => {
-                bytes.extend_from_slice(&[0x13]);
+                bytes.extend_from_slice(&[0x12]);
                 bytes.extend_from_slice(&bootstrap_method.to_be_bytes());
                 bytes.extend_from_slice(&name_and_type.to_be_bytes());
             }
             ConstantPoolEntry::Module(name) => {
-                bytes.extend_from_slice(&[0x16]);
+                bytes.extend_from_slice(&[0x13]);
                 bytes.extend_from_slice(&name.to_be_bytes());
             }
             ConstantPoolEntry::Package(name) => {
-                bytes.extend_from_slice(&[0x17]);
+                bytes.extend_from_slice(&[0x14]);
                 bytes.extend_from_slice(&name.to_be_bytes());
             }
         }
@@ -107,13 +134,222 @@ impl From<ConstantPoolEntry> for Vec<u8> {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The kind of a method handle, which determines how its member is accessed.
+/// See https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-5.html#jvms-5.4.3.5
+pub enum MethodHandleKind {
+    GetField = 1,
+    GetStatic = 2,
+    PutField = 3,
+    PutStatic = 4,
+    InvokeVirtual = 5,
+    InvokeStatic = 6,
+    InvokeSpecial = 7,
+    NewInvokeSpecial = 8,
+    InvokeInterface = 9,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A reference to a field or method, used as a loadable constant and for bootstrap methods.
+pub struct MethodHandle {
+    kind: MethodHandleKind,
+    class: String,
+    name: String,
+    descriptor: String,
+    is_interface: bool,
+}
+
+impl MethodHandle {
+    /// Creates a new [MethodHandle] referencing the member `name` of `class`.
+    pub fn new(
+        kind: MethodHandleKind,
+        class: impl Into<String>,
+        name: impl Into<String>,
+        descriptor: impl Into<String>,
+    ) -> Self {
+        Self {
+            kind,
+            class: class.into(),
+            name: name.into(),
+            descriptor: descriptor.into(),
+            is_interface: kind == MethodHandleKind::InvokeInterface,
+        }
+    }
+
+    /// Marks the class containing the method as an interface. This is needed for
+    /// [MethodHandleKind::InvokeStatic] and [MethodHandleKind::InvokeSpecial] handles to interface
+    /// methods.
+    pub fn interface(mut self) -> Self {
+        self.is_interface = true;
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A bootstrap method, used to link an `invokedynamic` call site or compute a dynamic constant.
+/// Bootstrap methods are stored in the class's `BootstrapMethods` attribute, and identical
+/// bootstrap methods are shared.
+pub struct BootstrapMethod {
+    handle: MethodHandle,
+    arguments: Vec<Constant>,
+}
+
+impl BootstrapMethod {
+    /// Creates a new [BootstrapMethod] which calls `handle`.
+    pub fn new(handle: MethodHandle) -> Self {
+        Self {
+            handle,
+            arguments: Vec::new(),
+        }
+    }
+
+    /// Adds a static argument, which is passed to the bootstrap method after the lookup, name and
+    /// type.
+    pub fn argument(mut self, argument: impl Into<Constant>) -> Self {
+        self.arguments.push(argument.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A constant which can be pushed onto the operand stack with `ldc`, `ldc_w` or `ldc2_w`.
+pub enum Constant {
+    Integer(i32),
+    Float(f32),
+    Long(i64),
+    Double(f64),
+    /// A `java/lang/String` literal.
+    String(String),
+    /// A `java/lang/Class`, given by its internal name or array descriptor.
+    Class(String),
+    /// A `java/lang/invoke/MethodType`, given by its method descriptor.
+    MethodType(String),
+    MethodHandle(MethodHandle),
+    /// A dynamically-computed constant of the type `descriptor`, produced by `bootstrap_method`.
+    Dynamic {
+        name: String,
+        descriptor: String,
+        bootstrap_method: BootstrapMethod,
+    },
+}
+
+impl Constant {
+    /// Returns whether the constant is a `long` or `double`, which must be loaded with `ldc2_w`.
+    pub fn is_wide(&self) -> bool {
+        match self {
+            Constant::Long(_) | Constant::Double(_) => true,
+            Constant::Dynamic { descriptor, .. } => descriptor == "J" || descriptor == "D",
+            _ => false,
+        }
+    }
+
+    /// Returns the field descriptor of the value pushed by loading the constant.
+    pub fn descriptor(&self) -> String {
+        match self {
+            Constant::Integer(_) => "I",
+            Constant::Float(_) => "F",
+            Constant::Long(_) => "J",
+            Constant::Double(_) => "D",
+            Constant::String(_) => "Ljava/lang/String;",
+            Constant::Class(_) => "Ljava/lang/Class;",
+            Constant::MethodType(_) => "Ljava/lang/invoke/MethodType;",
+            Constant::MethodHandle(_) => "Ljava/lang/invoke/MethodHandle;",
+            Constant::Dynamic { descriptor, .. } => descriptor,
+        }
+        .to_string()
+    }
+}
+
+/// Floating point constants are compared by their bits, as that is how they are stored in the
+/// constant pool.
+impl PartialEq for Constant {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Constant::Integer(a), Constant::Integer(b)) => a == b,
+            (Constant::Float(a), Constant::Float(b)) => a.to_bits() == b.to_bits(),
+            (Constant::Long(a), Constant::Long(b)) => a == b,
+            (Constant::Double(a), Constant::Double(b)) => a.to_bits() == b.to_bits(),
+            (Constant::String(a), Constant::String(b)) => a == b,
+            (Constant::Class(a), Constant::Class(b)) => a == b,
+            (Constant::MethodType(a), Constant::MethodType(b)) => a == b,
+            (Constant::MethodHandle(a), Constant::MethodHandle(b)) => a == b,
+            (
+                Constant::Dynamic {
+                    name,
+                    descriptor,
+                    bootstrap_method,
+                },
+                Constant::Dynamic {
+                    name: other_name,
+                    descriptor: other_descriptor,
+                    bootstrap_method: other_bootstrap_method,
+                },
+            ) => {
+                name == other_name
+                    && descriptor == other_descriptor
+                    && bootstrap_method == other_bootstrap_method
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Constant {}
+
+impl From<i32> for Constant {
+    fn from(value: i32) -> Self {
+        Constant::Integer(value)
+    }
+}
+
+impl From<f32> for Constant {
+    fn from(value: f32) -> Self {
+        Constant::Float(value)
+    }
+}
+
+impl From<i64> for Constant {
+    fn from(value: i64) -> Self {
+        Constant::Long(value)
+    }
+}
+
+impl From<f64> for Constant {
+    fn from(value: f64) -> Self {
+        Constant::Double(value)
+    }
+}
+
+impl From<&str> for Constant {
+    fn from(value: &str) -> Self {
+        Constant::String(value.to_string())
+    }
+}
+
+impl From<String> for Constant {
+    fn from(value: String) -> Self {
+        Constant::String(value)
+    }
+}
+
+impl From<MethodHandle> for Constant {
+    fn from(value: MethodHandle) -> Self {
+        Constant::MethodHandle(value)
+    }
+}
+
 #[derive(Debug, Clone)]
 /// Represents the constant pool of a class file.
 /// See https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.4
 pub struct ConstantPool {
     entries: Vec<ConstantPoolEntry>,
-    // TODO: Cache more than just string values
-    cache: HashMap<String, u16>,
+    /// The number of slots used by the entries. Long and double entries take two slots.
+    slots: u16,
+    /// Maps the encoding of each entry to its index, so that identical entries are shared.
+    cache: HashMap<Vec<u8>, u16>,
+    /// The entries of the class's `BootstrapMethods` attribute, as the index of a method handle
+    /// and the indices of its static arguments.
+    bootstrap_methods: Vec<(u16, Vec<u16>)>,
 }
 
 impl ConstantPool {
@@ -121,63 +357,92 @@ impl ConstantPool {
     pub fn new() -> Self {
         Self {
             entries: Vec::new(),
+            slots: 0,
             cache: HashMap::new(),
+            bootstrap_methods: Vec::new(),
         }
     }
 
-    /// Inserts a new string into the constant pool.
-    /// This is cached, so if the string already exists in the constant pool, the index of the existing string is returned.
-    pub fn insert_string(&mut self, s: impl Into<String>) -> u16
-    {
-        let s = s.into();
-        if let Some(index) = self.cache.get(&s) {
+    /// Inserts an entry into the constant pool, returning its index.
+    /// If an identical entry already exists in the constant pool, its index is returned instead.
+    fn insert(&mut self, entry: ConstantPoolEntry) -> u16 {
+        let key: Vec<u8> = entry.clone().into();
+        if let Some(index) = self.cache.get(&key) {
             return *index;
         }
 
-        self.entries.push(ConstantPoolEntry::String(s.clone()));
-        let index = self.entries.len().try_into().unwrap();
-        self.cache.insert(s, index);
+        let index = self.slots + 1;
+        self.slots += match entry {
+            ConstantPoolEntry::Long(_) | ConstantPoolEntry::Double(_) => 2,
+            _ => 1,
+        };
+        self.entries.push(entry);
+        self.cache.insert(key, index);
         index
     }
 
+    /// Inserts a new string into the constant pool.
+    /// This is cached, so if the string already exists in the constant pool, the index of the existing string is returned.
+    pub fn insert_string(&mut self, s: impl Into<String>) -> u16
+    {
+        self.insert(ConstantPoolEntry::String(s.into()))
+    }
+
     /// Inserts a new integer into the constant pool.
     pub fn insert_integer(&mut self, i: i32) -> u16 {
-        self.entries.push(ConstantPoolEntry::Integer(i));
-        self.entries.len().try_into().unwrap()
+        self.insert(ConstantPoolEntry::Integer(i))
     }
 
     /// Inserts a new float into the constant pool.
     pub fn insert_float(&mut self, f: f32) -> u16 {
-        self.entries.push(ConstantPoolEntry::Float(f));
-        self.entries.len().try_into().unwrap()
+        self.insert(ConstantPoolEntry::Float(f))
     }
 
     /// Inserts a new long into the constant pool.
     pub fn insert_long(&mut self, l: i64) -> u16 {
-        self.entries.push(ConstantPoolEntry::Long(l));
-        self.entries.len().try_into().unwrap()
+        self.insert(ConstantPoolEntry::Long(l))
     }
 
     /// Inserts a new double into the constant pool.
     pub fn insert_double(&mut self, d: f64) -> u16 {
-        self.entries.push(ConstantPoolEntry::Double(d));
-        self.entries.len().try_into().unwrap()
+        self.insert(ConstantPoolEntry::Double(d))
+    }
+
+    /// Inserts a loadable constant into the constant pool.
+    pub fn insert_constant(&mut self, constant: &Constant) -> u16 {
+        match constant {
+            Constant::Integer(i) => self.insert_integer(*i),
+            Constant::Float(f) => self.insert_float(*f),
+            Constant::Long(l) => self.insert_long(*l),
+            Constant::Double(d) => self.insert_double(*d),
+            Constant::String(s) => self.insert_string_reference(s),
+            Constant::Class(class) => self.insert_class(class),
+            Constant::MethodType(descriptor) => self.insert_method_type(descriptor),
+            Constant::MethodHandle(handle) => self.insert_method_handle(handle),
+            Constant::Dynamic {
+                name,
+                descriptor,
+                bootstrap_method,
+            } => {
+                let bootstrap_method = self.insert_bootstrap_method(bootstrap_method);
+                let name_and_type = self.insert_name_and_type(name, descriptor);
+                self.insert_dynamic(bootstrap_method, name_and_type)
+            }
+        }
     }
 
     /// Inserts a new class into the constant pool.
     pub fn insert_class(&mut self, s: impl Into<String>) -> u16
     {
         let index = self.insert_string(s);
-        self.entries.push(ConstantPoolEntry::Class(index));
-        self.entries.len().try_into().unwrap()
+        self.insert(ConstantPoolEntry::Class(index))
     }
 
     /// Inserts a string reference into the constant pool.
     pub fn insert_string_reference(&mut self, s:  impl Into<String>) -> u16
     {
         let index = self.insert_string(s);
-        self.entries.push(ConstantPoolEntry::StringReference(index));
-        self.entries.len().try_into().unwrap()
+        self.insert(ConstantPoolEntry::StringReference(index))
     }
 
     /// Inserts a new field reference into the constant pool.
@@ -185,9 +450,7 @@ impl ConstantPool {
     {
         let class_index = self.insert_class(class);
         let name_and_type_index = self.insert_name_and_type(field, descriptor);
-        self.entries
-            .push(ConstantPoolEntry::Field(class_index, name_and_type_index));
-        self.entries.len().try_into().unwrap()
+        self.insert(ConstantPoolEntry::Field(class_index, name_and_type_index))
     }
 
     /// Inserts a new method reference into the constant pool.
@@ -195,9 +458,7 @@ impl ConstantPool {
     {
         let class_index = self.insert_class(class);
         let name_and_type_index = self.insert_name_and_type(method, descriptor);
-        self.entries
-            .push(ConstantPoolEntry::Method(class_index, name_and_type_index));
-        self.entries.len().try_into().unwrap()
+        self.insert(ConstantPoolEntry::Method(class_index, name_and_type_index))
     }
 
     /// Inserts a new interface method reference into the constant pool.
@@ -205,11 +466,7 @@ impl ConstantPool {
     {
         let interface_index = self.insert_class(interface);
         let name_and_type_index = self.insert_name_and_type(method, descriptor);
-        self.entries.push(ConstantPoolEntry::InterfaceMethod(
-            interface_index,
-            name_and_type_index,
-        ));
-        self.entries.len().try_into().unwrap()
+        self.insert(ConstantPoolEntry::InterfaceMethod(interface_index, name_and_type_index))
     }
 
     /// Inserts a new name and type into the constant pool.
@@ -217,58 +474,98 @@ impl ConstantPool {
     {
         let name_index = self.insert_string(name);
         let descriptor_index = self.insert_string(descriptor);
-        self.entries
-            .push(ConstantPoolEntry::NameAndType(name_index, descriptor_index));
-        self.entries.len().try_into().unwrap()
+        self.insert(ConstantPoolEntry::NameAndType(name_index, descriptor_index))
+    }
+
+    /// Inserts a new method handle into the constant pool.
+    pub fn insert_method_handle(&mut self, handle: &MethodHandle) -> u16 {
+        let reference = match handle.kind {
+            MethodHandleKind::GetField
+            | MethodHandleKind::GetStatic
+            | MethodHandleKind::PutField
+            | MethodHandleKind::PutStatic => {
+                self.insert_field(&handle.class, &handle.name, &handle.descriptor)
+            }
+            _ if handle.is_interface => {
+                self.insert_interface_method(&handle.class, &handle.name, &handle.descriptor)
+            }
+            _ => self.insert_method(&handle.class, &handle.name, &handle.descriptor),
+        };
+        self.insert(ConstantPoolEntry::MethodHandle(handle.kind as u8, reference))
     }
 
     /// Inserts a new method type into the constant pool.
     pub fn insert_method_type(&mut self, descriptor: impl Into<String>) -> u16
     {
         let descriptor_index = self.insert_string(descriptor);
-        self.entries
-            .push(ConstantPoolEntry::MethodType(descriptor_index));
-        self.entries.len().try_into().unwrap()
+        self.insert(ConstantPoolEntry::MethodType(descriptor_index))
     }
 
-    /// Inserts a new method handle into the constant pool.
+    /// Adds a bootstrap method to the `BootstrapMethods` attribute, returning its index in the
+    /// attribute. If an identical bootstrap method has already been added, its index is returned.
+    pub fn insert_bootstrap_method(&mut self, bootstrap_method: &BootstrapMethod) -> u16 {
+        let handle = self.insert_method_handle(&bootstrap_method.handle);
+        let arguments: Vec<u16> = bootstrap_method
+            .arguments
+            .iter()
+            .map(|argument| self.insert_constant(argument))
+            .collect();
+        let entry = (handle, arguments);
+
+        match self.bootstrap_methods.iter().position(|other| *other == entry) {
+            Some(index) => index as u16,
+            None => {
+                self.bootstrap_methods.push(entry);
+                (self.bootstrap_methods.len() - 1) as u16
+            }
+        }
+    }
+
+    /// Returns the entries of the `BootstrapMethods` attribute, as the index of a method handle
+    /// and the indices of its static arguments.
+    pub fn bootstrap_methods(&self) -> &[(u16, Vec<u16>)] {
+        &self.bootstrap_methods
+    }
+
+    /// Inserts a new dynamically-computed constant into the constant pool.
+    /// `bootstrap_method` is an index into the `BootstrapMethods` attribute.
     pub fn insert_dynamic(&mut self, bootstrap_method: u16, name_and_type: u16) -> u16 {
-        self.entries
-            .push(ConstantPoolEntry::Dynamic(bootstrap_method, name_and_type));
-        self.entries.len().try_into().unwrap()
+        self.insert(ConstantPoolEntry::Dynamic(bootstrap_method, name_and_type))
     }
 
-    /// Inserts a new method handle into the constant pool.
+    /// Inserts a new dynamically-computed call site into the constant pool.
+    /// `bootstrap_method` is an index into the `BootstrapMethods` attribute.
     pub fn insert_invoke_dynamic(&mut self, bootstrap_method: u16, name_and_type: u16) -> u16 {
-        self.entries.push(ConstantPoolEntry::InvokeDynamic(
-            bootstrap_method,
-            name_and_type,
-        ));
-        self.entries.len().try_into().unwrap()
+        self.insert(ConstantPoolEntry::InvokeDynamic(bootstrap_method, name_and_type))
     }
 
     /// Insert a new module reference into the constant pool.
     pub fn insert_module(&mut self, name: impl Into<String>) -> u16
     {
         let name_index = self.insert_string(name);
-        self.entries.push(ConstantPoolEntry::Module(name_index));
-        self.entries.len().try_into().unwrap()
+        self.insert(ConstantPoolEntry::Module(name_index))
     }
 
     /// Insert a new package reference into the constant pool.
     pub fn insert_package(&mut self, name: impl Into<String>) -> u16
     {
         let name_index = self.insert_string(name);
-        self.entries.push(ConstantPoolEntry::Package(name_index));
-        self.entries.len().try_into().unwrap()
+        self.insert(ConstantPoolEntry::Package(name_index))
     }
 
     /// Emit the constant pool as a byte vector.
+    /// Returns [Error::StringTooLong] if a string does not fit in 65535 bytes of modified UTF-8.
     pub fn emit(self) -> Result<Vec<u8>, Error> {
         let mut bytes = Vec::new();
-        bytes.extend_from_slice(&(self.entries.len() as u16 + 1).to_be_bytes());
+        bytes.extend_from_slice(&(self.slots + 1).to_be_bytes());
 
         for entry in self.entries {
+            if let ConstantPoolEntry::String(s) = &entry {
+                let len = modified_utf8(s).len();
+                if len > u16::MAX as usize {
+                    return Err(Error::StringTooLong(len));
+                }
+            }
             bytes.extend::<Vec<u8>>(entry.into());
         }
 
@@ -0,0 +1,72 @@
+use crate::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A parsed method descriptor, such as `(ILjava/lang/String;)V`.
+/// See https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.3.3
+pub struct MethodDescriptor {
+    pub parameters: Vec<String>,
+    pub return_type: String,
+}
+
+impl MethodDescriptor {
+    /// Parses a method descriptor.
+    pub fn parse(descriptor: &str) -> Result<Self, Error> {
+        let invalid = || Error::InvalidDescriptor(descriptor.to_string());
+        let rest = descriptor.strip_prefix('(').ok_or_else(invalid)?;
+        let (mut parameters_str, return_type) = rest.split_once(')').ok_or_else(invalid)?;
+
+        let mut parameters = Vec::new();
+        while !parameters_str.is_empty() {
+            let length = field_type_length(parameters_str).ok_or_else(invalid)?;
+            parameters.push(parameters_str[..length].to_string());
+            parameters_str = &parameters_str[length..];
+        }
+
+        if return_type != "V" && field_type_length(return_type) != Some(return_type.len()) {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            parameters,
+            return_type: return_type.to_string(),
+        })
+    }
+
+    /// Returns the number of local variable slots taken by the parameters.
+    pub fn parameter_slots(&self) -> u16 {
+        self.parameters.iter().map(|p| slot_size(p)).sum()
+    }
+}
+
+/// Returns the length of the field type at the start of `descriptor`, or [None] if it does not
+/// start with a valid field type.
+fn field_type_length(descriptor: &str) -> Option<usize> {
+    match descriptor.chars().next()? {
+        'B' | 'C' | 'D' | 'F' | 'I' | 'J' | 'S' | 'Z' => Some(1),
+        'L' => descriptor
+            .find(';')
+            .filter(|end| *end > 1)
+            .map(|end| end + 1),
+        '[' => field_type_length(&descriptor[1..]).map(|length| length + 1),
+        _ => None,
+    }
+}
+
+/// Checks that `descriptor` is a single valid field type, such as `I` or `Ljava/lang/String;`.
+pub fn validate_field_type(descriptor: &str) -> Result<(), Error> {
+    if field_type_length(descriptor) == Some(descriptor.len()) {
+        Ok(())
+    } else {
+        Err(Error::InvalidDescriptor(descriptor.to_string()))
+    }
+}
+
+/// Returns the number of stack or local variable slots taken by a value of the given type.
+/// `long` and `double` take two slots, `void` takes none, and everything else takes one.
+pub fn slot_size(descriptor: &str) -> u16 {
+    match descriptor {
+        "J" | "D" => 2,
+        "V" => 0,
+        _ => 1,
+    }
+}
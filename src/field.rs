@@ -0,0 +1,145 @@
+use crate::{
+    access_flag::AccessFlag,
+    annotation::Annotation,
+    attribute::Attribute,
+    constant_pool::{Constant, ConstantPool},
+    descriptor::validate_field_type,
+    Error,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A builder for an individual field.
+pub struct FieldBuilder {
+    access_flags: Vec<AccessFlag>,
+    name: Option<String>,
+    descriptor: Option<String>,
+    constant_value: Option<Constant>,
+    signature: Option<String>,
+    annotations: Vec<Annotation>,
+}
+
+impl FieldBuilder {
+    /// Creates a new [FieldBuilder].
+    pub fn new() -> Self {
+        Self {
+            access_flags: Vec::new(),
+            name: None,
+            descriptor: None,
+            constant_value: None,
+            signature: None,
+            annotations: Vec::new(),
+        }
+    }
+
+    /// Adds an access flag to the field.
+    /// 'Access' flags also include modifiers such as `final` or `volatile`.
+    pub fn access_flag(mut self, flag: AccessFlag) -> Self {
+        self.access_flags.push(flag);
+        self
+    }
+
+    /// Sets the name of the field.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Sets the type of the field.
+    /// This is in the internal format, `Lcom/example/ExampleClass;`.
+    pub fn descriptor(mut self, descriptor: impl Into<String>) -> Self {
+        self.descriptor = Some(descriptor.into());
+        self
+    }
+
+    /// Sets the value the field is initialized to when the class is loaded, like the value of a
+    /// `static final` constant in Java. The field must be static, and the constant must be an
+    /// `int`, `float`, `long`, `double` or `String` matching the field's type.
+    pub fn constant_value(mut self, value: impl Into<Constant>) -> Self {
+        self.constant_value = Some(value.into());
+        self
+    }
+
+    /// Sets the generic signature of the field, such as `Ljava/util/List<Ljava/lang/String;>;`.
+    pub fn signature(mut self, signature: impl Into<String>) -> Self {
+        self.signature = Some(signature.into());
+        self
+    }
+
+    /// Adds an annotation to the field.
+    pub fn annotation(mut self, annotation: Annotation) -> Self {
+        self.annotations.push(annotation);
+        self
+    }
+
+    /// Emits the field to a vector of bytes.
+    /// Returns [Error::InvalidConstantValue] if the field has a constant value that does not match
+    /// its type, or is not static.
+    pub fn emit(self, constant_pool: &mut ConstantPool) -> Result<Vec<u8>, Error> {
+        let mut bytes = Vec::new();
+
+        let is_static = self.access_flags.contains(&AccessFlag::Static);
+        bytes.extend_from_slice(
+            &self
+                .access_flags
+                .into_iter()
+                .fold(0, |acc, flag| acc | <AccessFlag as Into<u16>>::into(flag))
+                .to_be_bytes(),
+        );
+
+        let name_index = constant_pool.insert_string(self.name.unwrap());
+        bytes.extend_from_slice(&name_index.to_be_bytes());
+
+        let descriptor = self.descriptor.unwrap();
+        validate_field_type(&descriptor)?;
+
+        let mut attributes = Vec::new();
+        if let Some(value) = self.constant_value {
+            let matches = matches!(
+                (&value, descriptor.as_str()),
+                (Constant::Integer(_), "I" | "S" | "C" | "B" | "Z")
+                    | (Constant::Float(_), "F")
+                    | (Constant::Long(_), "J")
+                    | (Constant::Double(_), "D")
+                    | (Constant::String(_), "Ljava/lang/String;")
+            );
+            if !matches || !is_static {
+                return Err(Error::InvalidConstantValue(descriptor));
+            }
+            attributes.push(Attribute::ConstantValue { value });
+        }
+        if let Some(signature) = self.signature {
+            attributes.push(Attribute::Signature { signature });
+        }
+        let (visible, invisible): (Vec<_>, Vec<_>) = self
+            .annotations
+            .into_iter()
+            .partition(|annotation| annotation.visible);
+        if !visible.is_empty() {
+            attributes.push(Attribute::RuntimeVisibleAnnotations {
+                annotations: visible,
+            });
+        }
+        if !invisible.is_empty() {
+            attributes.push(Attribute::RuntimeInvisibleAnnotations {
+                annotations: invisible,
+            });
+        }
+
+        let descriptor_index = constant_pool.insert_string(descriptor);
+        bytes.extend_from_slice(&descriptor_index.to_be_bytes());
+
+        bytes.extend_from_slice(&(attributes.len() as u16).to_be_bytes());
+
+        for attribute in attributes {
+            bytes.extend::<Vec<u8>>(attribute.emit(constant_pool)?);
+        }
+
+        Ok(bytes)
+    }
+}
+
+impl Default for FieldBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
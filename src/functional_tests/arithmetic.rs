@@ -0,0 +1,173 @@
+use crate::instruction::*;
+
+use super::*;
+
+#[test]
+fn int_arithmetic() {
+    let mut main = CodeBuilder::new();
+    let counter = main.new_local("I");
+    let main = main.instructions([
+        // (((((((5 - 2) * 5 / 2 % 4) neg) << 2) >> 1) >>> 28) & 5 | 4) ^ 1
+        iconst(5),
+        iconst(2),
+        isub(),
+        iconst(5),
+        imul(),
+        iconst(2),
+        idiv(),
+        iconst(4),
+        irem(),
+        ineg(),
+        iconst(2),
+        ishl(),
+        iconst(1),
+        ishr(),
+        // Shift distances are masked to 5 bits, so -4 shifts by 28.
+        iconst(-1),
+        iconst(4),
+        imul(),
+        iushr(),
+        iconst(5),
+        iand(),
+        iconst(4),
+        ior(),
+        iconst(1),
+        ixor(),
+        invokestatic("java/lang/Integer", "valueOf", "(I)Ljava/lang/Integer;"),
+        print(),
+        iconst(0),
+        istore(counter),
+        iinc(counter, 300),
+        iinc(counter, -1),
+        iload(counter),
+        invokestatic("java/lang/Integer", "valueOf", "(I)Ljava/lang/Integer;"),
+        print(),
+        r#return(),
+    ]);
+
+    run_bytecode(test_class(main).emit().unwrap(), "4\n299\n");
+}
+
+#[test]
+fn long_arithmetic() {
+    let mut main = CodeBuilder::new();
+    let a = main.new_local("J");
+    let main = main.instructions([
+        iconst(5),
+        invokestatic("java/lang/Integer", "toUnsignedLong", "(I)J"),
+        lstore(a),
+        // (((((-((a * a + a) / a % a) << 4) >>> 60) & a) ^ a) | a) >> 1
+        lload(a),
+        lload(a),
+        lmul(),
+        lload(a),
+        ladd(),
+        lload(a),
+        ldiv(),
+        lload(a),
+        lrem(),
+        lneg(),
+        iconst(4),
+        lshl(),
+        // Shift distances are masked to 6 bits, so -4 shifts by 60.
+        iconst(-1),
+        iconst(4),
+        imul(),
+        lushr(),
+        lload(a),
+        land(),
+        lload(a),
+        lxor(),
+        lload(a),
+        lor(),
+        iconst(1),
+        lshr(),
+        invokestatic("java/lang/Long", "valueOf", "(J)Ljava/lang/Long;"),
+        print(),
+        r#return(),
+    ]);
+
+    run_bytecode(test_class(main).emit().unwrap(), "2\n");
+}
+
+#[test]
+fn float_and_double_arithmetic() {
+    let mut main = CodeBuilder::new();
+    let five = main.new_local("Ljava/lang/Integer;");
+    let f = main.new_local("F");
+    let d = main.new_local("D");
+    let main = main.instructions([
+        iconst(5),
+        invokestatic("java/lang/Integer", "valueOf", "(I)Ljava/lang/Integer;"),
+        astore(five),
+        aload(five),
+        invokevirtual("java/lang/Integer", "floatValue", "()F"),
+        fstore(f),
+        aload(five),
+        invokevirtual("java/lang/Integer", "doubleValue", "()D"),
+        dstore(d),
+        // -((f * f + f) / f - f) % f
+        fload(f),
+        fload(f),
+        fmul(),
+        fload(f),
+        fadd(),
+        fload(f),
+        fdiv(),
+        fload(f),
+        fsub(),
+        fneg(),
+        fload(f),
+        frem(),
+        invokestatic("java/lang/Float", "valueOf", "(F)Ljava/lang/Float;"),
+        print(),
+        // -((d * d + d) / d - d) % d
+        dload(d),
+        dload(d),
+        dmul(),
+        dload(d),
+        dadd(),
+        dload(d),
+        ddiv(),
+        dload(d),
+        dsub(),
+        dneg(),
+        dload(d),
+        drem(),
+        invokestatic("java/lang/Double", "valueOf", "(D)Ljava/lang/Double;"),
+        print(),
+        r#return(),
+    ]);
+
+    run_bytecode(test_class(main).emit().unwrap(), "-1.0\n-1.0\n");
+}
+
+#[test]
+fn iinc_forms() {
+    let code = CodeBuilder::new().instructions([
+        iinc(0, 1),
+        iinc(0, -128),
+        iinc(0, 300),
+        iinc(256, 1),
+        r#return(),
+    ]);
+
+    match build_code(code).unwrap() {
+        Attribute::Code {
+            code, max_locals, ..
+        } => {
+            assert_eq!(
+                code,
+                vec![
+                    0x84, 0x00, 0x01, // iinc 0 1
+                    0x84, 0x00, 0x80, // iinc 0 -128
+                    0xc4, 0x84, 0x00, 0x00, 0x01, 0x2c, // wide iinc 0 300
+                    0xc4, 0x84, 0x01, 0x00, 0x00, 0x01, // wide iinc 256 1
+                    0xb1,
+                ]
+            );
+            assert_eq!(max_locals, 257);
+        }
+        _ => unreachable!(),
+    }
+}
@@ -0,0 +1,212 @@
+use crate::instruction::*;
+
+use super::*;
+
+/// Stores `value` into a new one-element array of `ty`, then loads and prints it.
+fn round_trip(
+    ty: ArrayType,
+    value: Instruction,
+    store: Instruction,
+    load: Instruction,
+    boxed: &str,
+) -> Vec<Instruction> {
+    let element = &ty.descriptor()[1..];
+    vec![
+        iconst(1),
+        newarray(ty),
+        astore(1),
+        aload(1),
+        iconst(0),
+        value,
+        store,
+        aload(1),
+        iconst(0),
+        load,
+        invokestatic(
+            format!("java/lang/{}", boxed),
+            "valueOf",
+            format!("({})Ljava/lang/{};", element, boxed),
+        ),
+        print(),
+    ]
+}
+
+#[test]
+fn primitive_arrays() {
+    let main = CodeBuilder::new()
+        .instructions(round_trip(
+            ArrayType::Int,
+            push_int(-7),
+            iastore(),
+            iaload(),
+            "Integer",
+        ))
+        .instructions(round_trip(
+            ArrayType::Long,
+            push_long(1 << 40),
+            lastore(),
+            laload(),
+            "Long",
+        ))
+        .instructions(round_trip(
+            ArrayType::Float,
+            push_float(0.5),
+            fastore(),
+            faload(),
+            "Float",
+        ))
+        .instructions(round_trip(
+            ArrayType::Double,
+            push_double(2.5),
+            dastore(),
+            daload(),
+            "Double",
+        ))
+        .instructions(round_trip(
+            ArrayType::Byte,
+            push_int(200),
+            bastore(),
+            baload(),
+            "Byte",
+        ))
+        .instructions(round_trip(
+            ArrayType::Boolean,
+            iconst(1),
+            bastore(),
+            baload(),
+            "Boolean",
+        ))
+        .instructions(round_trip(
+            ArrayType::Char,
+            push_int(65),
+            castore(),
+            caload(),
+            "Character",
+        ))
+        .instructions(round_trip(
+            ArrayType::Short,
+            push_int(70_000),
+            sastore(),
+            saload(),
+            "Short",
+        ))
+        .instructions([iconst(3), newarray(ArrayType::Int), arraylength()])
+        .instructions([
+            invokestatic("java/lang/Integer", "valueOf", "(I)Ljava/lang/Integer;"),
+            print(),
+            r#return(),
+        ]);
+
+    run_bytecode(
+        test_class(main).emit().unwrap(),
+        "-7\n1099511627776\n0.5\n2.5\n-56\ntrue\nA\n4464\n3\n",
+    );
+}
+
+#[test]
+fn reference_arrays() {
+    let mut main = CodeBuilder::new();
+    let strings = main.new_local("[Ljava/lang/String;");
+    let grid = main.new_local("[[I");
+    let main = main.instructions([
+        iconst(2),
+        anewarray("java/lang/String"),
+        astore(strings),
+        aload(strings),
+        iconst(1),
+        ldc("hello"),
+        aastore(),
+        // The element type is known, so no cast is needed.
+        aload(strings),
+        iconst(1),
+        aaload(),
+        invokevirtual("java/lang/String", "length", "()I"),
+        invokestatic("java/lang/Integer", "valueOf", "(I)Ljava/lang/Integer;"),
+        print(),
+        aload(strings),
+        invokestatic(
+            "java/util/Arrays",
+            "toString",
+            "([Ljava/lang/Object;)Ljava/lang/String;",
+        ),
+        print(),
+        iconst(2),
+        iconst(3),
+        multianewarray("[[I", 2),
+        astore(grid),
+        aload(grid),
+        iconst(1),
+        aaload(),
+        iconst(2),
+        iconst(5),
+        iastore(),
+        aload(grid),
+        invokestatic(
+            "java/util/Arrays",
+            "deepToString",
+            "([Ljava/lang/Object;)Ljava/lang/String;",
+        ),
+        print(),
+        iconst(1),
+        anewarray("[I"),
+        arraylength(),
+        invokestatic("java/lang/Integer", "valueOf", "(I)Ljava/lang/Integer;"),
+        print(),
+        r#return(),
+    ]);
+
+    run_bytecode(
+        test_class(main).emit().unwrap(),
+        "5\n[null, hello]\n[[0, 0, 0], [0, 0, 5]]\n1\n",
+    );
+}
+
+#[test]
+fn array_instruction_encoding() {
+    let code = CodeBuilder::new().instructions([
+        iconst(1),
+        newarray(ArrayType::Long),
+        iconst(1),
+        anewarray("java/lang/Object"),
+        iconst(1),
+        iconst(2),
+        multianewarray("[[[Z", 2),
+        r#return(),
+    ]);
+
+    match build_code(code).unwrap() {
+        Attribute::Code {
+            code, max_stack, ..
+        } => {
+            assert_eq!(
+                code,
+                vec![
+                    0x04, 0xbc, 0x0b, // newarray long
+                    0x04, 0xbd, 0x00, 0x02, // anewarray java/lang/Object
+                    0x04, 0x05, 0xc5, 0x00, 0x04, 0x02, // multianewarray [[[Z 2
+                    0xb1,
+                ]
+            );
+            assert_eq!(max_stack, 4);
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn multianewarray_dimensions_are_checked() {
+    for dimensions in [0, 4] {
+        let code = CodeBuilder::new().instructions([
+            iconst(1),
+            iconst(1),
+            iconst(1),
+            iconst(1),
+            multianewarray("[[[Z", dimensions),
+            r#return(),
+        ]);
+        assert!(matches!(
+            build_code(code),
+            Err(Error::InvalidArrayDimensions(d)) if d == dimensions
+        ));
+    }
+}
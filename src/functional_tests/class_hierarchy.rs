@@ -0,0 +1,166 @@
+use crate::{
+    class_hierarchy::{ClassHierarchy, DefaultClassHierarchy},
+    instruction::{
+        getstatic, goto, iconst, ifeq, iload, invokestatic, invokevirtual, ireturn, label, r#return,
+    },
+};
+
+use super::*;
+
+fn class_with_super(name: &str, super_class: &str) -> Vec<u8> {
+    ClassFileBuilder::new()
+        .access_flag(AccessFlag::Public)
+        .class_name(name)
+        .super_class(super_class)
+        .method(
+            MethodBuilder::new()
+                .access_flag(AccessFlag::Public)
+                .name("<init>")
+                .code(CodeBuilder::new().max_locals(1).instructions([
+                    aload(0),
+                    invokespecial(super_class, "<init>", "()V"),
+                    r#return(),
+                ])),
+        )
+        .emit()
+        .unwrap()
+}
+
+#[test]
+fn common_super_class_of_core_classes() {
+    let hierarchy = DefaultClassHierarchy::new().class("com/example/Dog", "com/example/Animal");
+
+    assert_eq!(
+        hierarchy
+            .common_super_class("java/lang/Integer", "java/lang/Long")
+            .unwrap(),
+        "java/lang/Number"
+    );
+    assert_eq!(
+        hierarchy
+            .common_super_class(
+                "java/lang/NumberFormatException",
+                "java/lang/IllegalStateException"
+            )
+            .unwrap(),
+        "java/lang/RuntimeException"
+    );
+    assert_eq!(
+        hierarchy
+            .common_super_class("java/lang/Runnable", "java/lang/String")
+            .unwrap(),
+        "java/lang/Object"
+    );
+    assert_eq!(
+        hierarchy
+            .common_super_class("com/example/Dog", "com/example/Animal")
+            .unwrap(),
+        "com/example/Animal"
+    );
+    assert!(matches!(
+        hierarchy.common_super_class("com/example/Dog", "java/lang/String"),
+        Err(Error::UnknownClass(class)) if class == "com/example/Animal"
+    ));
+    assert!(matches!(
+        hierarchy.common_super_class("java/lang/String", "com/example/Cat"),
+        Err(Error::UnknownClass(class)) if class == "com/example/Cat"
+    ));
+}
+
+#[test]
+fn merged_types_use_the_class_hierarchy() {
+    let mut pick = CodeBuilder::new().max_locals(1);
+    let short = pick.new_label();
+    let end = pick.new_label();
+    let pick = pick.instructions([
+        iload(0),
+        ifeq(short),
+        iconst(1),
+        invokestatic("java/lang/Integer", "valueOf", "(I)Ljava/lang/Integer;"),
+        goto(end),
+        label(short),
+        iconst(2),
+        invokestatic("java/lang/Short", "valueOf", "(S)Ljava/lang/Short;"),
+        label(end),
+        invokevirtual("java/lang/Number", "intValue", "()I"),
+        ireturn(),
+    ]);
+
+    let bytes = ClassFileBuilder::new()
+        .access_flag(AccessFlag::Public)
+        .class_name("Test")
+        .method(default_constructor())
+        .method(
+            MethodBuilder::new()
+                .access_flag(AccessFlag::Public)
+                .access_flag(AccessFlag::Static)
+                .name("pick")
+                .parameter("I")
+                .r#return("I")
+                .code(pick),
+        )
+        .method(
+            MethodBuilder::new()
+                .access_flag(AccessFlag::Public)
+                .access_flag(AccessFlag::Static)
+                .name("main")
+                .parameter("[Ljava/lang/String;")
+                .code(CodeBuilder::new().max_locals(1).instructions([
+                    getstatic("java/lang/System", "out", "Ljava/io/PrintStream;"),
+                    iconst(0),
+                    invokestatic("Test", "pick", "(I)I"),
+                    invokevirtual("java/io/PrintStream", "println", "(I)V"),
+                    r#return(),
+                ])),
+        )
+        .emit()
+        .unwrap();
+
+    run_bytecode(bytes, "2\n");
+}
+
+#[test]
+fn classes_are_loaded_from_directories_and_jars() {
+    let dir = tempfile::TempDir::new().unwrap();
+    fs::create_dir_all(dir.path().join("com/example")).unwrap();
+    fs::write(
+        dir.path().join("com/example/Animal.class"),
+        class_with_super("com/example/Animal", "java/lang/Object"),
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("com/example/Dog.class"),
+        class_with_super("com/example/Dog", "com/example/Animal"),
+    )
+    .unwrap();
+
+    let hierarchy = DefaultClassHierarchy::empty()
+        .directory(dir.path())
+        .unwrap();
+    assert_eq!(
+        hierarchy.super_class_of("com/example/Dog").as_deref(),
+        Some("com/example/Animal")
+    );
+    assert!(!hierarchy.is_interface("com/example/Dog"));
+
+    #[cfg(feature = "jar")]
+    {
+        let jar = dir.path().join("classes.jar");
+        let status = std::process::Command::new("jar")
+            .arg("cf")
+            .arg(&jar)
+            .arg("com")
+            .current_dir(&dir)
+            .status()
+            .expect("failed to execute process");
+        assert!(status.success());
+
+        let hierarchy = DefaultClassHierarchy::empty().jar(&jar).unwrap();
+        assert_eq!(
+            hierarchy
+                .common_super_class("com/example/Dog", "com/example/Animal")
+                .unwrap(),
+            "com/example/Animal"
+        );
+    }
+}
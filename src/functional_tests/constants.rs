@@ -0,0 +1,255 @@
+use crate::{
+    constant_pool::{BootstrapMethod, Constant, MethodHandle, MethodHandleKind},
+    instruction::*,
+};
+
+use super::*;
+
+/// Boxes the primitive of type `descriptor` on top of the stack as a `java/lang/{class}`, and
+/// prints it.
+fn print_boxed(descriptor: &str, class: &str) -> [Instruction; 2] {
+    [
+        invokestatic(
+            format!("java/lang/{}", class),
+            "valueOf",
+            format!("({})Ljava/lang/{};", descriptor, class),
+        ),
+        print(),
+    ]
+}
+
+#[test]
+fn push_int_chooses_the_smallest_encoding() {
+    let code = CodeBuilder::new().instructions([
+        push_int(-1),
+        push_int(5),
+        push_int(6),
+        push_int(-128),
+        push_int(128),
+        push_int(-32768),
+        push_int(32768),
+        push_long(1),
+        push_long(2),
+        push_float(2.0),
+        push_float(-0.0),
+        push_double(0.0),
+        push_double(0.5),
+        r#return(),
+    ]);
+
+    assert_eq!(
+        code_bytes(code),
+        vec![
+            0x02, // iconst_m1
+            0x08, // iconst_5
+            0x10, 0x06, // bipush 6
+            0x10, 0x80, // bipush -128
+            0x11, 0x00, 0x80, // sipush 128
+            0x11, 0x80, 0x00, // sipush -32768
+            0x12, 0x01, // ldc #1
+            0x0a, // lconst_1
+            0x14, 0x00, 0x03, // ldc2_w #3
+            0x0d, // fconst_2
+            0x12, 0x02, // ldc #2
+            0x0e, // dconst_0
+            0x14, 0x00, 0x05, // ldc2_w #5
+            0xb1,
+        ]
+    );
+}
+
+#[test]
+fn pushed_constants_have_the_right_values() {
+    let main = CodeBuilder::new()
+        .instructions([push_int(100)])
+        .instructions(print_boxed("I", "Integer"))
+        .instructions([push_int(-1000)])
+        .instructions(print_boxed("I", "Integer"))
+        .instructions([push_int(i32::MAX)])
+        .instructions(print_boxed("I", "Integer"))
+        .instructions([push_long(i64::MIN)])
+        .instructions(print_boxed("J", "Long"))
+        .instructions([push_long(0)])
+        .instructions(print_boxed("J", "Long"))
+        .instructions([push_float(1.5)])
+        .instructions(print_boxed("F", "Float"))
+        .instructions([push_float(1.0)])
+        .instructions(print_boxed("F", "Float"))
+        .instructions([push_double(-0.0)])
+        .instructions(print_boxed("D", "Double"))
+        .instructions([push_double(1.0)])
+        .instructions(print_boxed("D", "Double"))
+        .instructions([push_int(i32::MAX)])
+        .instructions(print_boxed("I", "Integer"))
+        .instructions([r#return()]);
+
+    run_bytecode(
+        test_class(main).emit().unwrap(),
+        "100\n-1000\n2147483647\n-9223372036854775808\n0\n1.5\n1.0\n-0.0\n1.0\n2147483647\n",
+    );
+}
+
+#[test]
+fn ldc_is_widened_for_large_constant_pool_indices() {
+    let mut constant_pool = ConstantPool::new();
+    for i in 0..300 {
+        constant_pool.insert_integer(1000 + i);
+    }
+
+    let parameters = ["I".to_string()];
+    let method = MethodContext {
+        class_name: "Test",
+        name: "test",
+        parameters: &parameters,
+        is_static: true,
+        hierarchy: &DefaultClassHierarchy::new(),
+    };
+    let code = CodeBuilder::new()
+        .instructions([ldc(1000), ldc(5000), r#return()])
+        .build(&mut constant_pool, &method)
+        .unwrap();
+
+    match code {
+        Attribute::Code { code, .. } => {
+            assert_eq!(code, vec![0x12, 0x01, 0x13, 0x01, 0x2d, 0xb1]);
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn wide_constants_need_ldc2_w() {
+    assert!(matches!(
+        build_code(CodeBuilder::new().instructions([ldc(1i64), r#return()])),
+        Err(Error::InvalidConstant("ldc"))
+    ));
+    assert!(matches!(
+        build_code(CodeBuilder::new().instructions([ldc2_w(1), r#return()])),
+        Err(Error::InvalidConstant("ldc2_w"))
+    ));
+}
+
+#[test]
+fn ldc_loads_reference_constants() {
+    let main = CodeBuilder::new().instructions([
+        ldc("Hello, world!"),
+        print(),
+        ldc(Constant::Class("java/lang/String".to_string())),
+        print(),
+        ldc(Constant::Class("[I".to_string())),
+        print(),
+        ldc(Constant::MethodType("(IJ)Ljava/lang/String;".to_string())),
+        print(),
+        ldc(MethodHandle::new(
+            MethodHandleKind::InvokeStatic,
+            "java/lang/Integer",
+            "valueOf",
+            "(I)Ljava/lang/Integer;",
+        )),
+        print(),
+        ldc(MethodHandle::new(
+            MethodHandleKind::InvokeInterface,
+            "java/lang/Runnable",
+            "run",
+            "()V",
+        )),
+        print(),
+        ldc(MethodHandle::new(
+            MethodHandleKind::GetStatic,
+            "java/lang/System",
+            "out",
+            "Ljava/io/PrintStream;",
+        )),
+        print(),
+        r#return(),
+    ]);
+
+    run_bytecode(
+        test_class(main).emit().unwrap(),
+        "Hello, world!\nclass java.lang.String\nclass [I\n(int,long)String\n\
+         MethodHandle(int)Integer\nMethodHandle(Runnable)void\nMethodHandle()PrintStream\n",
+    );
+}
+
+#[test]
+fn strings_are_loaded_with_ldc_w_when_needed() {
+    let mut main = CodeBuilder::new();
+    for i in 0..200 {
+        main = main.instructions([ldc(format!("string {}", i)), astore(1)]);
+    }
+    let main = main.instructions([ldc("string 199"), print(), r#return()]);
+
+    run_bytecode(test_class(main).emit().unwrap(), "string 199\n");
+}
+
+#[test]
+fn strings_are_encoded_as_modified_utf8() {
+    let mut constant_pool = ConstantPool::new();
+    constant_pool.insert_string("a\0é😀");
+    assert_eq!(
+        constant_pool.emit().unwrap(),
+        vec![
+            0x00, 0x02, // constant_pool_count
+            0x01, 0x00, 0x0b, // CONSTANT_Utf8, length 11
+            0x61, // a
+            0xc0, 0x80, // \0
+            0xc3, 0xa9, // é
+            0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80, // 😀 as a surrogate pair
+        ]
+    );
+
+    let mut constant_pool = ConstantPool::new();
+    constant_pool.insert_string("\0".repeat(40000));
+    assert!(matches!(
+        constant_pool.emit(),
+        Err(Error::StringTooLong(80000))
+    ));
+}
+
+#[test]
+fn ldc_loads_strings_with_nul_and_supplementary_characters() {
+    let main = CodeBuilder::new()
+        .instructions([
+            ldc("a\0b😀"),
+            invokevirtual("java/lang/String", "length", "()I"),
+        ])
+        .instructions(print_boxed("I", "Integer"))
+        .instructions([
+            ldc("a\0b😀"),
+            iconst(1),
+            invokevirtual("java/lang/String", "codePointAt", "(I)I"),
+        ])
+        .instructions(print_boxed("I", "Integer"))
+        .instructions([
+            ldc("a\0b😀"),
+            iconst(3),
+            invokevirtual("java/lang/String", "codePointAt", "(I)I"),
+        ])
+        .instructions(print_boxed("I", "Integer"))
+        .instructions([r#return()]);
+
+    run_bytecode(test_class(main).emit().unwrap(), "5\n0\n128512\n");
+}
+
+#[test]
+fn ldc_loads_dynamic_constants() {
+    let get_static_final = BootstrapMethod::new(MethodHandle::new(
+        MethodHandleKind::InvokeStatic,
+        "java/lang/invoke/ConstantBootstraps",
+        "getStaticFinal",
+        "(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/Class;\
+         Ljava/lang/Class;)Ljava/lang/Object;",
+    ))
+    .argument(Constant::Class("java/lang/Integer".to_string()));
+
+    let main = CodeBuilder::new()
+        .instructions([ldc(Constant::Dynamic {
+            name: "MAX_VALUE".to_string(),
+            descriptor: "I".to_string(),
+            bootstrap_method: get_static_final,
+        })])
+        .instructions(print_boxed("I", "Integer"))
+        .instructions([r#return()]);
+
+    run_bytecode(test_class(main).emit().unwrap(), "2147483647\n");
+}
@@ -0,0 +1,82 @@
+use crate::instruction::*;
+
+use super::*;
+
+#[test]
+fn primitive_conversions() {
+    let main = CodeBuilder::new()
+        // (byte) 375
+        .instructions([
+            iconst(5),
+            iconst(5),
+            imul(),
+            iconst(5),
+            imul(),
+            iconst(3),
+            imul(),
+            i2b(),
+        ])
+        .instructions(print_int())
+        // (char) -1
+        .instructions([iconst(-1), i2c()])
+        .instructions(print_int())
+        // (short) (char) -1
+        .instructions([iconst(-1), i2c(), i2s()])
+        .instructions(print_int())
+        // (int) (long) (double) (3f / 2f)
+        .instructions([
+            iconst(3),
+            i2f(),
+            iconst(2),
+            i2f(),
+            fdiv(),
+            f2d(),
+            d2l(),
+            l2i(),
+        ])
+        .instructions(print_int())
+        .instructions([
+            iconst(5),
+            i2d(),
+            d2f(),
+            f2l(),
+            l2f(),
+            f2i(),
+            i2l(),
+            l2d(),
+            d2i(),
+        ])
+        .instructions(print_int())
+        .instructions([r#return()]);
+
+    run_bytecode(test_class(main).emit().unwrap(), "119\n65535\n-1\n1\n5\n");
+}
+
+#[test]
+fn comparisons() {
+    let nan_float = [iconst(0), i2f(), iconst(0), i2f(), fdiv()];
+    let nan_double = [iconst(0), i2d(), iconst(0), i2d(), ddiv()];
+
+    let main = CodeBuilder::new()
+        .instructions([iconst(5), i2l(), iconst(3), i2l(), lcmp()])
+        .instructions(print_int())
+        .instructions([iconst(3), i2l(), iconst(3), i2l(), lcmp()])
+        .instructions(print_int())
+        .instructions(nan_float.clone())
+        .instructions([iconst(1), i2f(), fcmpl()])
+        .instructions(print_int())
+        .instructions(nan_float)
+        .instructions([iconst(1), i2f(), fcmpg()])
+        .instructions(print_int())
+        .instructions(nan_double.clone())
+        .instructions([iconst(1), i2d(), dcmpl()])
+        .instructions(print_int())
+        .instructions(nan_double)
+        .instructions([iconst(1), i2d(), dcmpg()])
+        .instructions(print_int())
+        .instructions([iconst(1), i2d(), iconst(2), i2d(), dcmpg()])
+        .instructions(print_int())
+        .instructions([r#return()]);
+
+    run_bytecode(test_class(main).emit().unwrap(), "1\n0\n-1\n1\n-1\n1\n-1\n");
+}
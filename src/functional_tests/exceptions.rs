@@ -0,0 +1,324 @@
+use crate::{
+    attribute::try_builder::TryBuilder,
+    instruction::{
+        goto, iconst, if_icmplt, ifeq, ifne, iinc, iload, invokestatic, ireturn, istore, label,
+        nop, pop, r#return, Instruction,
+    },
+};
+
+use super::*;
+
+fn divide_by_zero() -> [Instruction; 5] {
+    [
+        iconst(1),
+        iconst(0),
+        invokestatic("java/lang/Math", "floorDiv", "(II)I"),
+        invokestatic("java/lang/Integer", "valueOf", "(I)Ljava/lang/Integer;"),
+        print(),
+    ]
+}
+
+#[test]
+fn try_catch() {
+    let mut main = CodeBuilder::new().max_locals(1);
+    let start = main.new_label();
+    let end = main.new_label();
+    let handler = main.new_label();
+    let main = main
+        .instructions([label(start)])
+        .instructions(divide_by_zero())
+        .instructions([label(end), r#return(), label(handler), print(), r#return()])
+        .try_catch(start, end, handler, Some("java/lang/ArithmeticException"));
+
+    run_bytecode(
+        test_class(main).emit().unwrap(),
+        "java.lang.ArithmeticException: / by zero\n",
+    );
+}
+
+#[test]
+fn handlers_are_tried_in_order() {
+    let mut main = CodeBuilder::new().max_locals(1);
+    let start = main.new_label();
+    let end = main.new_label();
+    let done = main.new_label();
+    let null_pointer = main.new_label();
+    let any = main.new_label();
+    let main = main
+        .instructions([label(start)])
+        .instructions(divide_by_zero())
+        .instructions([goto(done), iconst(3), label(end), label(done), r#return()])
+        .instructions([label(null_pointer), print()])
+        .instructions([iconst(1)])
+        .instructions(print_int())
+        .instructions([r#return(), label(any), print()])
+        .instructions([iconst(2)])
+        .instructions(print_int())
+        .instructions([r#return()])
+        .try_catch(
+            start,
+            end,
+            null_pointer,
+            Some("java/lang/NullPointerException"),
+        )
+        .try_catch(start, end, any, None);
+
+    run_bytecode(
+        test_class(main).emit().unwrap(),
+        "java.lang.ArithmeticException: / by zero\n2\n",
+    );
+}
+
+#[test]
+fn try_catch_finally_without_exception() {
+    let main = CodeBuilder::new()
+        .max_locals(1)
+        .try_block(
+            TryBuilder::new()
+                .body([iconst(1)])
+                .body(print_int())
+                .catch("java/lang/Exception", [print()])
+                .finally([iconst(3)].into_iter().chain(print_int())),
+        )
+        .instructions([r#return()]);
+
+    run_bytecode(test_class(main).emit().unwrap(), "1\n3\n");
+}
+
+#[test]
+fn try_catch_finally_with_caught_exception() {
+    let main = CodeBuilder::new()
+        .max_locals(1)
+        .try_block(
+            TryBuilder::new()
+                .body(divide_by_zero())
+                .catch("java/lang/ArithmeticException", [print()])
+                .finally([iconst(3)].into_iter().chain(print_int())),
+        )
+        .instructions([r#return()]);
+
+    run_bytecode(
+        test_class(main).emit().unwrap(),
+        "java.lang.ArithmeticException: / by zero\n3\n",
+    );
+}
+
+#[test]
+fn finally_runs_before_uncaught_exception_propagates() {
+    let run = CodeBuilder::new().try_block(
+        TryBuilder::new()
+            .body(divide_by_zero())
+            .catch("java/lang/NullPointerException", [print()])
+            .finally([iconst(3)].into_iter().chain(print_int())),
+    );
+    let run = run.instructions([r#return()]);
+
+    let mut main = CodeBuilder::new().max_locals(1);
+    let start = main.new_label();
+    let end = main.new_label();
+    let handler = main.new_label();
+    let main = main
+        .instructions([
+            label(start),
+            invokestatic("Test", "run", "()V"),
+            label(end),
+            r#return(),
+            label(handler),
+            print(),
+            r#return(),
+        ])
+        .try_catch(start, end, handler, None);
+
+    let bytes = test_class(main)
+        .method(
+            MethodBuilder::new()
+                .access_flag(AccessFlag::Static)
+                .name("run")
+                .code(run),
+        )
+        .emit()
+        .unwrap();
+
+    run_bytecode(bytes, "3\njava.lang.ArithmeticException: / by zero\n");
+}
+
+#[test]
+fn finally_runs_before_return() {
+    let value = CodeBuilder::new().try_block(
+        TryBuilder::new()
+            .body([iconst(1), ireturn()])
+            .finally([iconst(3)].into_iter().chain(print_int())),
+    );
+
+    let main = CodeBuilder::new().max_locals(1).instructions([
+        invokestatic("Test", "value", "()I"),
+        invokestatic("java/lang/Integer", "valueOf", "(I)Ljava/lang/Integer;"),
+        print(),
+        r#return(),
+    ]);
+
+    let bytes = test_class(main)
+        .method(
+            MethodBuilder::new()
+                .access_flag(AccessFlag::Static)
+                .name("value")
+                .r#return("I")
+                .code(value),
+        )
+        .emit()
+        .unwrap();
+
+    run_bytecode(bytes, "3\n1\n");
+}
+
+#[test]
+fn empty_try_body_with_handlers() {
+    let run = CodeBuilder::new()
+        .try_block(TryBuilder::new().catch("java/lang/Exception", [pop()]))
+        .try_block(
+            TryBuilder::new()
+                .catch("java/lang/Exception", [pop()])
+                .finally([nop()]),
+        )
+        .instructions([r#return()]);
+
+    let main = CodeBuilder::new()
+        .max_locals(1)
+        .instructions([invokestatic("Test", "run", "()V")])
+        .instructions([iconst(1)])
+        .instructions(print_int())
+        .instructions([r#return()]);
+
+    let bytes = test_class(main)
+        .method(
+            MethodBuilder::new()
+                .access_flag(AccessFlag::Static)
+                .name("run")
+                .code(run),
+        )
+        .emit()
+        .unwrap();
+
+    run_bytecode(bytes, "1\n");
+}
+
+#[test]
+fn finally_runs_with_an_empty_stack() {
+    // The `finally` block continues the loop until `i` reaches 3, discarding the value being
+    // returned or the exception being thrown, so neither can be left on the stack.
+    let mut value = CodeBuilder::new();
+    let head = value.new_label();
+    let i = value.new_local("I");
+    let value = value
+        .instructions([iconst(0), istore(i), label(head)])
+        .try_block(
+            TryBuilder::new()
+                .body([iinc(i, 1), iload(i), ireturn()])
+                .finally([iload(i), iconst(3), if_icmplt(head)]),
+        )
+        .instructions([iconst(0), ireturn()]);
+
+    let main = CodeBuilder::new().max_locals(1).instructions([
+        invokestatic("Test", "value", "()I"),
+        invokestatic("java/lang/Integer", "valueOf", "(I)Ljava/lang/Integer;"),
+        print(),
+        r#return(),
+    ]);
+
+    let bytes = test_class(main)
+        .method(
+            MethodBuilder::new()
+                .access_flag(AccessFlag::Static)
+                .name("value")
+                .r#return("I")
+                .code(value),
+        )
+        .emit()
+        .unwrap();
+
+    run_bytecode(bytes, "3\n");
+}
+
+#[test]
+fn branching_finally_is_copied_with_fresh_labels() {
+    let mut main = CodeBuilder::new().max_locals(1);
+    let skip = main.new_label();
+    let done = main.new_label();
+    let finally = [iconst(1), ifeq(skip)]
+        .into_iter()
+        .chain([iconst(3)])
+        .chain(print_int())
+        .chain([label(skip)]);
+    let main = main
+        .try_block(
+            TryBuilder::new()
+                .body([iconst(0), ifne(done)])
+                .body(divide_by_zero())
+                .catch("java/lang/ArithmeticException", [print()])
+                .finally(finally),
+        )
+        .instructions([label(done), r#return()]);
+
+    run_bytecode(
+        test_class(main).emit().unwrap(),
+        "java.lang.ArithmeticException: / by zero\n3\n",
+    );
+}
+
+#[test]
+fn nested_try_blocks() {
+    let mut run = CodeBuilder::new();
+    let inner = TryBuilder::new()
+        .body(divide_by_zero())
+        .finally([iconst(1)].into_iter().chain(print_int()))
+        .lower(&mut run);
+    let run = run
+        .try_block(
+            TryBuilder::new()
+                .body(inner)
+                .catch("java/lang/ArithmeticException", [print()])
+                .finally([iconst(2)].into_iter().chain(print_int())),
+        )
+        .instructions([r#return()]);
+
+    let mut value = CodeBuilder::new();
+    let inner = TryBuilder::new()
+        .body([iconst(4), ireturn()])
+        .finally([iconst(1)].into_iter().chain(print_int()))
+        .lower(&mut value);
+    let value = value.try_block(
+        TryBuilder::new()
+            .body(inner)
+            .finally([iconst(2)].into_iter().chain(print_int())),
+    );
+
+    let main = CodeBuilder::new().max_locals(1).instructions([
+        invokestatic("Test", "run", "()V"),
+        invokestatic("Test", "value", "()I"),
+        invokestatic("java/lang/Integer", "valueOf", "(I)Ljava/lang/Integer;"),
+        print(),
+        r#return(),
+    ]);
+
+    let bytes = test_class(main)
+        .method(
+            MethodBuilder::new()
+                .access_flag(AccessFlag::Static)
+                .name("run")
+                .code(run),
+        )
+        .method(
+            MethodBuilder::new()
+                .access_flag(AccessFlag::Static)
+                .name("value")
+                .r#return("I")
+                .code(value),
+        )
+        .emit()
+        .unwrap();
+
+    run_bytecode(
+        bytes,
+        "1\njava.lang.ArithmeticException: / by zero\n2\n1\n2\n4\n",
+    );
+}
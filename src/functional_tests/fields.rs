@@ -0,0 +1,226 @@
+use crate::{
+    annotation::{Annotation, ElementValue},
+    constant_pool::Constant,
+    field::FieldBuilder,
+    instruction::*,
+};
+
+use super::*;
+
+fn constant(name: &str, descriptor: &str, value: impl Into<Constant>) -> FieldBuilder {
+    FieldBuilder::new()
+        .access_flag(AccessFlag::Public)
+        .access_flag(AccessFlag::Static)
+        .access_flag(AccessFlag::Final)
+        .name(name)
+        .descriptor(descriptor)
+        .constant_value(value)
+}
+
+/// Pushes the `java.lang.reflect.Field` named `name` of the class `Test`.
+fn reflect_field(name: &str) -> [Instruction; 3] {
+    [
+        ldc(Constant::Class("Test".to_string())),
+        ldc(name),
+        invokevirtual(
+            "java/lang/Class",
+            "getDeclaredField",
+            "(Ljava/lang/String;)Ljava/lang/reflect/Field;",
+        ),
+    ]
+}
+
+#[test]
+fn static_final_constants() {
+    let main = CodeBuilder::new().instructions([
+        getstatic("Test", "ANSWER", "I"),
+        invokestatic("java/lang/Integer", "valueOf", "(I)Ljava/lang/Integer;"),
+        print(),
+        getstatic("Test", "BIG", "J"),
+        invokestatic("java/lang/Long", "valueOf", "(J)Ljava/lang/Long;"),
+        print(),
+        getstatic("Test", "HALF", "D"),
+        invokestatic("java/lang/Double", "valueOf", "(D)Ljava/lang/Double;"),
+        print(),
+        getstatic("Test", "GREETING", "Ljava/lang/String;"),
+        print(),
+        r#return(),
+    ]);
+
+    let bytes = test_class(main)
+        .field(constant("ANSWER", "I", 42))
+        .field(constant("BIG", "J", 1i64 << 40))
+        .field(constant("HALF", "D", 0.5))
+        .field(constant("GREETING", "Ljava/lang/String;", "hello"))
+        .emit()
+        .unwrap();
+
+    run_bytecode(bytes, "42\n1099511627776\n0.5\nhello\n");
+}
+
+#[test]
+fn instance_field() {
+    let main = CodeBuilder::new().instructions([
+        new("Test"),
+        dup(),
+        invokespecial("Test", "<init>", "()V"),
+        dup(),
+        dup(),
+        push_int(7),
+        putfield("Test", "count", "I"),
+        getfield("Test", "count", "I"),
+        invokestatic("java/lang/Integer", "valueOf", "(I)Ljava/lang/Integer;"),
+        print(),
+        getfield("Test", "name", "Ljava/lang/String;"),
+        print(),
+        r#return(),
+    ]);
+
+    let bytes = test_class(main)
+        .field(
+            FieldBuilder::new()
+                .access_flag(AccessFlag::Private)
+                .name("count")
+                .descriptor("I"),
+        )
+        .field(
+            FieldBuilder::new()
+                .access_flag(AccessFlag::Volatile)
+                .name("name")
+                .descriptor("Ljava/lang/String;"),
+        )
+        .emit()
+        .unwrap();
+
+    run_bytecode(bytes, "7\nnull\n");
+}
+
+#[test]
+fn field_signature_and_annotations() {
+    let main = CodeBuilder::new()
+        .instructions(reflect_field("names"))
+        .instructions([
+            invokevirtual(
+                "java/lang/reflect/Field",
+                "getGenericType",
+                "()Ljava/lang/reflect/Type;",
+            ),
+            print(),
+        ])
+        .instructions(reflect_field("names"))
+        .instructions([
+            ldc(Constant::Class("java/lang/Deprecated".to_string())),
+            invokevirtual(
+                "java/lang/reflect/Field",
+                "getAnnotation",
+                "(Ljava/lang/Class;)Ljava/lang/annotation/Annotation;",
+            ),
+            checkcast("java/lang/Deprecated"),
+            invokeinterface("java/lang/Deprecated", "since", "()Ljava/lang/String;"),
+            print(),
+        ])
+        .instructions(reflect_field("names"))
+        .instructions([
+            invokevirtual(
+                "java/lang/reflect/Field",
+                "getAnnotations",
+                "()[Ljava/lang/annotation/Annotation;",
+            ),
+            arraylength(),
+            invokestatic("java/lang/Integer", "valueOf", "(I)Ljava/lang/Integer;"),
+            print(),
+            r#return(),
+        ]);
+
+    let bytes = test_class(main)
+        .field(
+            FieldBuilder::new()
+                .name("names")
+                .descriptor("Ljava/util/List;")
+                .signature("Ljava/util/List<Ljava/lang/String;>;")
+                .annotation(Annotation::new("Ljava/lang/Deprecated;").element("since", "9"))
+                .annotation(
+                    Annotation::new("Lcom/example/Generated;")
+                        .element("count", 3)
+                        .invisible(),
+                ),
+        )
+        .emit()
+        .unwrap();
+
+    run_bytecode(bytes, "java.util.List<java.lang.String>\n9\n1\n");
+}
+
+#[test]
+fn element_values() {
+    let annotation = Annotation::new("LA;")
+        .element("b", 1i8)
+        .element(
+            "e",
+            ElementValue::Enum {
+                descriptor: "LE;".to_string(),
+                name: "X".to_string(),
+            },
+        )
+        .element(
+            "a",
+            vec![
+                ElementValue::Boolean(true),
+                ElementValue::Class("V".to_string()),
+            ],
+        )
+        .element("n", Annotation::new("LB;"));
+
+    let mut constant_pool = ConstantPool::new();
+    let bytes = annotation.emit(&mut constant_pool);
+
+    let utf8 = |pool: &mut ConstantPool, s: &str| pool.insert_string(s).to_be_bytes();
+    let integer = |pool: &mut ConstantPool, i: i32| pool.insert_integer(i).to_be_bytes();
+    let mut expected = Vec::new();
+    expected.extend(utf8(&mut constant_pool, "LA;"));
+    expected.extend(4u16.to_be_bytes());
+    expected.extend(utf8(&mut constant_pool, "b"));
+    expected.push(b'B');
+    expected.extend(integer(&mut constant_pool, 1));
+    expected.extend(utf8(&mut constant_pool, "e"));
+    expected.push(b'e');
+    expected.extend(utf8(&mut constant_pool, "LE;"));
+    expected.extend(utf8(&mut constant_pool, "X"));
+    expected.extend(utf8(&mut constant_pool, "a"));
+    expected.push(b'[');
+    expected.extend(2u16.to_be_bytes());
+    expected.push(b'Z');
+    expected.extend(integer(&mut constant_pool, 1));
+    expected.push(b'c');
+    expected.extend(utf8(&mut constant_pool, "V"));
+    expected.extend(utf8(&mut constant_pool, "n"));
+    expected.push(b'@');
+    expected.extend(utf8(&mut constant_pool, "LB;"));
+    expected.extend(0u16.to_be_bytes());
+
+    assert_eq!(bytes, expected);
+}
+
+#[test]
+fn invalid_constant_values() {
+    let emit = |field: FieldBuilder| field.emit(&mut ConstantPool::new());
+
+    assert!(matches!(
+        emit(constant("X", "J", 1)),
+        Err(Error::InvalidConstantValue(descriptor)) if descriptor == "J"
+    ));
+    assert!(matches!(
+        emit(constant("X", "Ljava/lang/Object;", "value")),
+        Err(Error::InvalidConstantValue(_))
+    ));
+    assert!(matches!(
+        emit(
+            FieldBuilder::new()
+                .name("X")
+                .descriptor("I")
+                .constant_value(1)
+        ),
+        Err(Error::InvalidConstantValue(_))
+    ));
+    assert!(emit(constant("X", "Z", 1)).is_ok());
+}
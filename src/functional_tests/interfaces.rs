@@ -0,0 +1,99 @@
+use crate::instruction::*;
+
+use super::*;
+
+#[test]
+fn interface_methods_are_invoked() {
+    let main = CodeBuilder::new().instructions([
+        ldc("a"),
+        ldc("b"),
+        invokestatic_interface(
+            "java/util/List",
+            "of",
+            "(Ljava/lang/Object;Ljava/lang/Object;)Ljava/util/List;",
+        ),
+        invokeinterface("java/util/List", "size", "()I"),
+        invokestatic("java/lang/Integer", "valueOf", "(I)Ljava/lang/Integer;"),
+        print(),
+        ldc("key"),
+        ldc("value"),
+        invokestatic_interface(
+            "java/util/Map",
+            "of",
+            "(Ljava/lang/Object;Ljava/lang/Object;)Ljava/util/Map;",
+        ),
+        ldc("missing"),
+        ldc("default"),
+        invokeinterface(
+            "java/util/Map",
+            "getOrDefault",
+            "(Ljava/lang/Object;Ljava/lang/Object;)Ljava/lang/Object;",
+        ),
+        print(),
+        r#return(),
+    ]);
+
+    run_bytecode(test_class(main).emit().unwrap(), "2\ndefault\n");
+}
+
+#[test]
+fn invokeinterface_count_includes_wide_arguments() {
+    let operator = "java/util/function/LongBinaryOperator";
+    let code = CodeBuilder::new().instructions([
+        ldc("receiver"),
+        push_long(1),
+        push_long(2),
+        invokeinterface(operator, "applyAsLong", "(JJ)J"),
+        pop2(),
+        ldc("receiver"),
+        push_long(1),
+        push_long(2),
+        invokespecial_interface(operator, "applyAsLong", "(JJ)J"),
+        pop2(),
+        r#return(),
+    ]);
+
+    let code = code_bytes(code);
+    assert_eq!(code[6], 0xb9);
+    // The receiver and two longs take 5 slots.
+    assert_eq!(&code[9..11], &[5, 0]);
+    assert_eq!(code[18], 0xb7);
+    // Both calls use the same InterfaceMethodref.
+    assert_eq!(code[7..9], code[19..21]);
+    assert_eq!(code.len(), 23);
+}
+
+#[test]
+fn class_implements_interfaces() {
+    let main = CodeBuilder::new().instructions([
+        new("Test"),
+        dup(),
+        invokespecial("Test", "<init>", "()V"),
+        dup(),
+        invokeinterface("java/lang/Runnable", "run", "()V"),
+        instanceof("java/lang/AutoCloseable"),
+        invokestatic("java/lang/Boolean", "valueOf", "(Z)Ljava/lang/Boolean;"),
+        print(),
+        r#return(),
+    ]);
+
+    let bytes = test_class(main)
+        .interface("java/lang/Runnable")
+        .interface("java/lang/AutoCloseable")
+        .method(
+            MethodBuilder::new()
+                .access_flag(AccessFlag::Public)
+                .name("run")
+                .code(CodeBuilder::new().instructions([ldc("run"), print(), r#return()])),
+        )
+        .method(
+            MethodBuilder::new()
+                .access_flag(AccessFlag::Public)
+                .name("close")
+                .code(CodeBuilder::new().instructions([r#return()])),
+        )
+        .emit()
+        .unwrap();
+
+    run_bytecode(bytes, "run\ntrue\n");
+}
@@ -0,0 +1,94 @@
+use crate::{
+    constant_pool::{BootstrapMethod, Constant, MethodHandle, MethodHandleKind},
+    instruction::*,
+};
+
+use super::*;
+
+fn string_concat(recipe: &str) -> BootstrapMethod {
+    BootstrapMethod::new(MethodHandle::new(
+        MethodHandleKind::InvokeStatic,
+        "java/lang/invoke/StringConcatFactory",
+        "makeConcatWithConstants",
+        "(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/invoke/MethodType;\
+         Ljava/lang/String;[Ljava/lang/Object;)Ljava/lang/invoke/CallSite;",
+    ))
+    .argument(recipe)
+}
+
+#[test]
+fn invokedynamic_string_concatenation() {
+    let main = CodeBuilder::new().instructions([
+        ldc("world"),
+        invokedynamic(
+            "makeConcatWithConstants",
+            "(Ljava/lang/String;)Ljava/lang/String;",
+            string_concat("Hello, \u{1}!"),
+        ),
+        print(),
+        ldc("again"),
+        push_long(2),
+        invokedynamic(
+            "makeConcatWithConstants",
+            "(Ljava/lang/String;J)Ljava/lang/String;",
+            string_concat("Hello \u{1} x\u{1}"),
+        ),
+        print(),
+        ldc("jaby"),
+        invokedynamic(
+            "makeConcatWithConstants",
+            "(Ljava/lang/String;)Ljava/lang/String;",
+            string_concat("Hello, \u{1}!"),
+        ),
+        print(),
+        r#return(),
+    ]);
+
+    run_bytecode(
+        test_class(main).emit().unwrap(),
+        "Hello, world!\nHello again x2\nHello, jaby!\n",
+    );
+}
+
+#[test]
+fn dynamic_constants() {
+    let bootstrap_method = BootstrapMethod::new(MethodHandle::new(
+        MethodHandleKind::InvokeStatic,
+        "java/lang/invoke/ConstantBootstraps",
+        "invoke",
+        "(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/Class;\
+         Ljava/lang/invoke/MethodHandle;[Ljava/lang/Object;)Ljava/lang/Object;",
+    ))
+    .argument(MethodHandle::new(
+        MethodHandleKind::InvokeStatic,
+        "java/lang/Long",
+        "sum",
+        "(JJ)J",
+    ))
+    .argument(40i64)
+    .argument(2i64);
+
+    let main = CodeBuilder::new().instructions([
+        ldc2_w(Constant::Dynamic {
+            name: "answer".to_string(),
+            descriptor: "J".to_string(),
+            bootstrap_method,
+        }),
+        invokestatic("java/lang/Long", "valueOf", "(J)Ljava/lang/Long;"),
+        print(),
+        r#return(),
+    ]);
+
+    run_bytecode(test_class(main).emit().unwrap(), "42\n");
+}
+
+#[test]
+fn bootstrap_methods_are_deduplicated() {
+    let mut constant_pool = ConstantPool::new();
+    let first = constant_pool.insert_bootstrap_method(&string_concat("\u{1}"));
+    let second = constant_pool.insert_bootstrap_method(&string_concat("\u{1}\u{1}"));
+    let third = constant_pool.insert_bootstrap_method(&string_concat("\u{1}"));
+
+    assert_eq!((first, second, third), (0, 1, 0));
+    assert_eq!(constant_pool.bootstrap_methods().len(), 2);
+}
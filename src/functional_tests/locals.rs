@@ -0,0 +1,88 @@
+use crate::{
+    instruction::{
+        astore, dload, dstore, fload, fstore, iload, invokestatic, istore, lload, lstore, r#return,
+    },
+    local::LocalIndex,
+};
+
+use super::*;
+
+#[test]
+fn local_indices_from_integers() {
+    let slot: u8 = 3;
+    assert_eq!(iload(slot), iload(3u16));
+    assert_eq!(
+        astore(300),
+        Instruction::astore {
+            index: LocalIndex::Slot(300)
+        }
+    );
+}
+
+#[test]
+fn load_and_store_forms() {
+    let code = CodeBuilder::new().instructions([
+        iload(0),
+        istore(3),
+        iload(4),
+        istore(255),
+        lload(256),
+        lstore(1000),
+        fload(2),
+        fstore(5),
+        dload(1),
+        dstore(2),
+        aload(4),
+        astore(0),
+        r#return(),
+    ]);
+
+    match build_code(code).unwrap() {
+        Attribute::Code {
+            code, max_locals, ..
+        } => {
+            assert_eq!(
+                code,
+                vec![
+                    0x1a, // iload_0
+                    0x3e, // istore_3
+                    0x15, 0x04, // iload 4
+                    0x36, 0xff, // istore 255
+                    0xc4, 0x16, 0x01, 0x00, // wide lload 256
+                    0xc4, 0x37, 0x03, 0xe8, // wide lstore 1000
+                    0x24, // fload_2
+                    0x38, 0x05, // fstore 5
+                    0x27, // dload_1
+                    0x49, // dstore_2
+                    0x19, 0x04, // aload 4
+                    0x4b, // astore_0
+                    0xb1,
+                ]
+            );
+            assert_eq!(max_locals, 1002);
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn values_round_trip_through_locals() {
+    let mut main = CodeBuilder::new();
+    let time = main.new_local("J");
+    let main = main.instructions([
+        invokestatic("java/lang/System", "nanoTime", "()J"),
+        lstore(time),
+        lload(time),
+        lload(time),
+        invokestatic("java/lang/Long", "compare", "(JJ)I"),
+        istore(300),
+        iload(300),
+        invokestatic("java/lang/Integer", "valueOf", "(I)Ljava/lang/Integer;"),
+        astore(2),
+        aload(2),
+        print(),
+        r#return(),
+    ]);
+
+    run_bytecode(test_class(main).emit().unwrap(), "0\n");
+}
@@ -1,14 +1,35 @@
 use crate::{
-    attribute::code::CodeBuilder,
+    attribute::{
+        code::{CodeBuilder, MethodContext},
+        Attribute,
+    },
+    class_hierarchy::DefaultClassHierarchy,
+    constant_pool::ConstantPool,
     instruction::{
-        aload, getstatic, iadd, iconst, iload, invokespecial, invokestatic, invokevirtual, ireturn,
-        r#return,
+        aload, astore, getstatic, goto, i2l, iadd, iconst, if_icmple, ifeq, iinc, iload,
+        invokespecial, invokestatic, invokevirtual, ireturn, label, lload, lstore, pop, r#return,
     },
 };
 
 use super::*;
 use std::fs;
 
+mod arithmetic;
+mod arrays;
+mod class_hierarchy;
+mod constants;
+mod conversions;
+mod exceptions;
+mod fields;
+mod interfaces;
+mod invokedynamic;
+mod locals;
+mod objects;
+mod returns;
+mod stack;
+mod switches;
+mod synchronized;
+
 pub fn run_bytecode(bytes: Vec<u8>, expected_output: impl Into<String>)
 {
     let expected_output = expected_output.into();
@@ -46,6 +67,93 @@ pub fn run_bytecode(bytes: Vec<u8>, expected_output: impl Into<String>)
     assert_eq!(stderr, "");
 }
 
+/// Builds a code attribute for `static void test(int)` in the class `Test`.
+pub fn build_code(code: CodeBuilder) -> Result<Attribute, Error> {
+    let parameters = ["I".to_string()];
+    let method = MethodContext {
+        class_name: "Test",
+        name: "test",
+        parameters: &parameters,
+        is_static: true,
+        hierarchy: &DefaultClassHierarchy::new(),
+    };
+
+    code.build(&mut ConstantPool::new(), &method)
+}
+
+/// Builds a code attribute for `static void test(int)` and returns its bytecode, without running it.
+pub fn code_bytes(code: CodeBuilder) -> Vec<u8> {
+    match build_code(code).unwrap() {
+        Attribute::Code { code, .. } => code,
+        _ => unreachable!(),
+    }
+}
+
+/// A public no-argument constructor that calls `super()`.
+pub fn default_constructor() -> MethodBuilder {
+    MethodBuilder::new()
+        .access_flag(AccessFlag::Public)
+        .name("<init>")
+        .code(CodeBuilder::new().max_locals(1).instructions([
+            aload(0),
+            invokespecial("java/lang/Object", "<init>", "()V"),
+            r#return(),
+        ]))
+}
+
+/// Calls [print_method] with the value on top of the stack.
+pub fn print() -> Instruction {
+    invokestatic("Test", "print", "(Ljava/lang/Object;)V")
+}
+
+/// Prints the `int` on top of the stack with [print_method].
+pub fn print_int() -> [Instruction; 2] {
+    [
+        invokestatic("java/lang/Integer", "valueOf", "(I)Ljava/lang/Integer;"),
+        print(),
+    ]
+}
+
+/// Prints the `long` on top of the stack with [print_method].
+pub fn print_long() -> [Instruction; 2] {
+    [
+        invokestatic("java/lang/Long", "valueOf", "(J)Ljava/lang/Long;"),
+        print(),
+    ]
+}
+
+/// `static void print(Object)`, which prints its argument with `System.out.println`.
+pub fn print_method() -> MethodBuilder {
+    MethodBuilder::new()
+        .access_flag(AccessFlag::Public)
+        .access_flag(AccessFlag::Static)
+        .name("print")
+        .parameter("Ljava/lang/Object;")
+        .code(CodeBuilder::new().max_locals(1).instructions([
+            getstatic("java/lang/System", "out", "Ljava/io/PrintStream;"),
+            aload(0),
+            invokevirtual("java/io/PrintStream", "println", "(Ljava/lang/Object;)V"),
+            r#return(),
+        ]))
+}
+
+/// A class named `Test` with a default constructor, [print_method], and the given `main` method body.
+pub fn test_class(main: CodeBuilder) -> ClassFileBuilder {
+    ClassFileBuilder::new()
+        .access_flag(AccessFlag::Public)
+        .class_name("Test")
+        .method(default_constructor())
+        .method(print_method())
+        .method(
+            MethodBuilder::new()
+                .access_flag(AccessFlag::Public)
+                .access_flag(AccessFlag::Static)
+                .name("main")
+                .parameter("[Ljava/lang/String;")
+                .code(main),
+        )
+}
+
 #[test]
 fn add_two_numbers() {
     let bytes = ClassFileBuilder::new()
@@ -69,7 +177,7 @@ fn add_two_numbers() {
                 .parameter("[Ljava/lang/String;".to_string())
                 .r#return("V".to_string())
                 .code(CodeBuilder::new().max_locals(1).instructions([
-                    getstatic("java/lang/System", "out", "Ljava/io/PrintStream"),
+                    getstatic("java/lang/System", "out", "Ljava/io/PrintStream;"),
                     iconst(2),
                     iconst(2),
                     iadd(),
@@ -121,7 +229,7 @@ fn add_two_numbers_via_a_method() {
                 .parameter("[Ljava/lang/String;".to_string())
                 .r#return("V".to_string())
                 .code(CodeBuilder::new().max_locals(1).instructions([
-                    getstatic("java/lang/System", "out", "Ljava/io/PrintStream"),
+                    getstatic("java/lang/System", "out", "Ljava/io/PrintStream;"),
                     iconst(2),
                     iconst(2),
                     invokestatic("Test", "add", "(II)I"),
@@ -138,3 +246,344 @@ fn add_two_numbers_via_a_method() {
 
     run_bytecode(bytes, "8\n".to_string());
 }
+
+#[test]
+fn branch_offsets_are_resolved() {
+    let mut code = CodeBuilder::new().max_locals(1);
+    let start = code.new_label();
+    let end = code.new_label();
+    let code = code.instructions([
+        label(start),
+        iload(0),
+        ifeq(end),
+        goto(start),
+        label(end),
+        r#return(),
+    ]);
+
+    assert_eq!(
+        code_bytes(code),
+        vec![0x1a, 0x99, 0x00, 0x06, 0xa7, 0xff, 0xfc, 0xb1]
+    );
+}
+
+#[test]
+fn unplaced_label_is_an_error() {
+    let mut code = CodeBuilder::new();
+    let end = code.new_label();
+    let code = code.instructions([goto(end)]);
+
+    assert!(matches!(build_code(code), Err(Error::UnplacedLabel)));
+}
+
+#[test]
+fn out_of_range_branches_are_widened() {
+    let mut code = CodeBuilder::new().max_locals(1);
+    let start = code.new_label();
+    let end = code.new_label();
+    let code = code
+        .instructions([label(start), iload(0), ifeq(end), iload(0)])
+        .instructions([iload(0), iadd()].into_iter().cycle().take(40_000))
+        .instructions([ifeq(start), label(end), r#return()]);
+
+    let code = code_bytes(code);
+
+    // `ifeq end` becomes `ifne skip; goto_w end; skip:`.
+    assert_eq!(&code[1..4], &[0x9a, 0x00, 0x08]);
+    assert_eq!(code[4], 0xc8);
+    assert_eq!(&code[5..9], &(40_018i32 - 4).to_be_bytes());
+    // `ifeq start` becomes `ifne skip; goto_w start; skip:`.
+    assert_eq!(&code[40_010..40_013], &[0x9a, 0x00, 0x08]);
+    assert_eq!(code[40_013], 0xc8);
+    assert_eq!(&code[40_014..40_018], &(-40_013i32).to_be_bytes());
+    assert_eq!(code.len(), 40_019);
+}
+
+#[test]
+fn code_larger_than_64_kib_is_an_error() {
+    let code = CodeBuilder::new()
+        .instructions(
+            [iload(0), iload(0), iadd()]
+                .into_iter()
+                .cycle()
+                .take(65_535),
+        )
+        .instructions([r#return()]);
+
+    assert!(matches!(build_code(code), Err(Error::CodeTooLarge(65_536))));
+}
+
+#[test]
+fn branches_have_stack_map_frames() {
+    let mut max = CodeBuilder::new().max_locals(2);
+    let second = max.new_label();
+    let max = max.instructions([
+        iload(0),
+        iload(1),
+        if_icmple(second),
+        iload(0),
+        ireturn(),
+        label(second),
+        iload(1),
+        ireturn(),
+    ]);
+
+    let mut print = CodeBuilder::new().max_locals(1);
+    let zero = print.new_label();
+    let end = print.new_label();
+    let print = print.instructions([
+        getstatic("java/lang/System", "out", "Ljava/io/PrintStream;"),
+        iload(0),
+        ifeq(zero),
+        iconst(1),
+        goto(end),
+        label(zero),
+        iconst(0),
+        label(end),
+        invokevirtual("java/io/PrintStream", "println", "(I)V"),
+        r#return(),
+    ]);
+
+    let bytes = ClassFileBuilder::new()
+        .access_flag(AccessFlag::Public)
+        .class_name("Test")
+        .method(default_constructor())
+        .method(
+            MethodBuilder::new()
+                .access_flag(AccessFlag::Public)
+                .access_flag(AccessFlag::Static)
+                .name("max")
+                .parameter("I")
+                .parameter("I")
+                .r#return("I")
+                .code(max),
+        )
+        .method(
+            MethodBuilder::new()
+                .access_flag(AccessFlag::Public)
+                .access_flag(AccessFlag::Static)
+                .name("print")
+                .parameter("I")
+                .code(print),
+        )
+        .method(
+            MethodBuilder::new()
+                .access_flag(AccessFlag::Public)
+                .access_flag(AccessFlag::Static)
+                .name("main")
+                .parameter("[Ljava/lang/String;")
+                .code(CodeBuilder::new().max_locals(1).instructions([
+                    iconst(2),
+                    iconst(5),
+                    invokestatic("Test", "max", "(II)I"),
+                    iconst(4),
+                    invokestatic("Test", "max", "(II)I"),
+                    invokestatic("Test", "print", "(I)V"),
+                    iconst(0),
+                    invokestatic("Test", "print", "(I)V"),
+                    r#return(),
+                ])),
+        )
+        .emit()
+        .unwrap();
+
+    run_bytecode(bytes, "1\n0\n");
+}
+
+#[test]
+fn unreachable_code_is_replaced() {
+    let mut code = CodeBuilder::new();
+    let end = code.new_label();
+    let code = code.instructions([goto(end), iconst(1), iconst(2), label(end), r#return()]);
+
+    assert_eq!(
+        code_bytes(code.clone()),
+        vec![0xa7, 0x00, 0x05, 0x00, 0xbf, 0xb1]
+    );
+    run_bytecode(test_class(code).emit().unwrap(), "");
+}
+
+#[test]
+fn invalid_field_descriptors_are_errors() {
+    for descriptor in ["", "Ljava/io/PrintStream", "V", "II"] {
+        let code = CodeBuilder::new().instructions([
+            getstatic("java/lang/System", "out", descriptor),
+            pop(),
+            r#return(),
+        ]);
+        assert!(matches!(
+            build_code(code),
+            Err(Error::InvalidDescriptor(d)) if d == descriptor
+        ));
+    }
+
+    let method = MethodBuilder::new()
+        .access_flag(AccessFlag::Static)
+        .name("test")
+        .parameter("")
+        .code(CodeBuilder::new().instructions([r#return()]));
+    assert!(matches!(
+        test_class(CodeBuilder::new().instructions([r#return()]))
+            .method(method)
+            .emit(),
+        Err(Error::InvalidDescriptor(_))
+    ));
+}
+
+#[test]
+fn max_stack_follows_descriptors_and_branches() {
+    let mut code = CodeBuilder::new();
+    let end = code.new_label();
+    let code = code.instructions([
+        invokestatic("java/lang/System", "nanoTime", "()J"),
+        invokestatic("java/lang/System", "nanoTime", "()J"),
+        invokestatic("java/lang/Long", "compare", "(JJ)I"),
+        ifeq(end),
+        getstatic("java/lang/System", "out", "Ljava/io/PrintStream;"),
+        iload(0),
+        iload(0),
+        iadd(),
+        invokevirtual("java/io/PrintStream", "println", "(I)V"),
+        label(end),
+        r#return(),
+    ]);
+
+    match build_code(code).unwrap() {
+        Attribute::Code { max_stack, .. } => assert_eq!(max_stack, 4),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn max_stack_has_room_for_unreachable_code_frames() {
+    let mut main = CodeBuilder::new();
+    let end = main.new_label();
+    let main = main.instructions([goto(end), iconst(1), iconst(2), label(end), r#return()]);
+
+    run_bytecode(test_class(main).emit().unwrap(), "");
+}
+
+#[test]
+fn max_locals_covers_parameters_and_accessed_locals() {
+    let max_locals = |code: CodeBuilder| match build_code(code).unwrap() {
+        Attribute::Code { max_locals, .. } => max_locals,
+        _ => unreachable!(),
+    };
+
+    assert_eq!(max_locals(CodeBuilder::new().instructions([r#return()])), 1);
+    assert_eq!(
+        max_locals(CodeBuilder::new().instructions([aload(3), r#return()])),
+        4
+    );
+    assert_eq!(
+        max_locals(CodeBuilder::new().max_locals(6).instructions([r#return()])),
+        6
+    );
+}
+
+#[test]
+fn max_locals_is_calculated_for_methods() {
+    let bytes = ClassFileBuilder::new()
+        .access_flag(AccessFlag::Public)
+        .class_name("Test")
+        .method(
+            MethodBuilder::new()
+                .access_flag(AccessFlag::Public)
+                .name("<init>")
+                .code(CodeBuilder::new().instructions([
+                    aload(0),
+                    invokespecial("java/lang/Object", "<init>", "()V"),
+                    r#return(),
+                ])),
+        )
+        .method(
+            MethodBuilder::new()
+                .access_flag(AccessFlag::Public)
+                .access_flag(AccessFlag::Static)
+                .name("add")
+                .parameter("J")
+                .parameter("I")
+                .r#return("I")
+                .code(CodeBuilder::new().instructions([iload(2), iload(2), iadd(), ireturn()])),
+        )
+        .method(
+            MethodBuilder::new()
+                .access_flag(AccessFlag::Public)
+                .access_flag(AccessFlag::Static)
+                .name("main")
+                .parameter("[Ljava/lang/String;")
+                .code(CodeBuilder::new().instructions([
+                    getstatic("java/lang/System", "out", "Ljava/io/PrintStream;"),
+                    invokestatic("java/lang/System", "nanoTime", "()J"),
+                    iconst(2),
+                    invokestatic("Test", "add", "(JI)I"),
+                    invokevirtual("java/io/PrintStream", "println", "(I)V"),
+                    r#return(),
+                ])),
+        )
+        .emit()
+        .unwrap();
+
+    run_bytecode(bytes, "4\n");
+}
+
+#[test]
+fn explicit_max_locals_is_raised_to_cover_locals() {
+    let mut code = CodeBuilder::new().max_locals(1);
+    let long = code.new_local("J");
+    let code = code.instructions([iload(0), i2l(), lstore(long), r#return()]);
+
+    match build_code(code).unwrap() {
+        Attribute::Code { max_locals, .. } => assert_eq!(max_locals, 3),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn locals_are_placed_after_parameters_and_reused_after_scopes() {
+    let mut code = CodeBuilder::new();
+    let long = code.new_local("J");
+    code.begin_scope();
+    let int = code.new_local("I");
+    code.end_scope();
+    let string = code.new_local("Ljava/lang/String;");
+    let code = code.instructions([iload(int), aload(string), iload(0), r#return()]);
+
+    assert_eq!(long.size(), 2);
+    match build_code(code).unwrap() {
+        Attribute::Code {
+            code, max_locals, ..
+        } => {
+            assert_eq!(code, vec![0x1d, 0x2d, 0x1a, 0xb1]);
+            assert_eq!(max_locals, 4);
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn locals_are_checked_against_their_type() {
+    let mut code = CodeBuilder::new();
+    let long = code.new_local("J");
+    let code = code.instructions([lload(long), iload(long), r#return()]);
+    assert!(matches!(
+        build_code(code),
+        Err(Error::LocalTypeMismatch(instruction, LocalType::Long))
+            if *instruction == iload(long)
+    ));
+
+    let mut code = CodeBuilder::new();
+    let flag = code.new_local("Z");
+    let array = code.new_local("[I");
+    let code = code.instructions([iinc(flag, 1), aload(array), astore(flag), r#return()]);
+    assert!(matches!(
+        build_code(code),
+        Err(Error::LocalTypeMismatch(_, LocalType::Int))
+    ));
+}
+
+#[test]
+#[should_panic(expected = "Invalid local variable type: V")]
+fn locals_must_have_a_field_type() {
+    CodeBuilder::new().new_local("V");
+}
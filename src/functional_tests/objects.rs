@@ -0,0 +1,111 @@
+use crate::instruction::*;
+
+use super::*;
+
+#[test]
+fn objects_are_created_and_their_fields_accessed() {
+    let mut main = CodeBuilder::new();
+    let point = main.new_local("Ljava/awt/Point;");
+    let main = main
+        .instructions([
+            new("java/awt/Point"),
+            astore(point),
+            aload(point),
+            invokespecial("java/awt/Point", "<init>", "()V"),
+            aload(point),
+            push_int(42),
+            putfield("java/awt/Point", "x", "I"),
+            aload(point),
+            getfield("java/awt/Point", "x", "I"),
+        ])
+        .instructions(print_int())
+        .instructions([r#return()]);
+
+    run_bytecode(test_class(main).emit().unwrap(), "42\n");
+}
+
+#[test]
+fn uninitialized_objects_have_frames() {
+    let mut main = CodeBuilder::new();
+    let builder = main.new_local("Ljava/lang/StringBuilder;");
+    let skip = main.new_label();
+    let main = main.instructions([
+        new("java/lang/StringBuilder"),
+        astore(builder),
+        aload(0),
+        arraylength(),
+        ifeq(skip),
+        label(skip),
+        aload(builder),
+        ldc("initialized"),
+        invokespecial("java/lang/StringBuilder", "<init>", "(Ljava/lang/String;)V"),
+        aload(builder),
+        print(),
+        r#return(),
+    ]);
+
+    run_bytecode(test_class(main).emit().unwrap(), "initialized\n");
+}
+
+#[test]
+fn casts_and_type_checks() {
+    let mut main = CodeBuilder::new();
+    let object = main.new_local("Ljava/lang/Object;");
+    let main = main
+        .instructions([
+            ldc("four"),
+            astore(object),
+            aload(object),
+            instanceof("java/lang/String"),
+        ])
+        .instructions(print_int())
+        .instructions([aload(object), instanceof("[Ljava/lang/String;")])
+        .instructions(print_int())
+        .instructions([
+            aload(object),
+            checkcast("java/lang/String"),
+            invokevirtual("java/lang/String", "length", "()I"),
+        ])
+        .instructions(print_int())
+        .instructions([aload(0), arraylength()])
+        .instructions(print_int())
+        .instructions([
+            aload(object),
+            checkcast("java/lang/Integer"),
+            print(),
+            r#return(),
+        ]);
+
+    let class = test_class(main).emit().unwrap();
+    let dir = tempfile::TempDir::new().unwrap();
+    fs::write(dir.path().join("Test.class"), class).unwrap();
+    let output = std::process::Command::new("java")
+        .arg("Test")
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "1\n0\n4\n0\n");
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("java.lang.ClassCastException"));
+}
+
+#[test]
+fn field_instructions_reference_the_constant_pool() {
+    let code = CodeBuilder::new().instructions([
+        getstatic("Test", "counter", "J"),
+        putstatic("Test", "counter", "J"),
+        r#return(),
+    ]);
+
+    match build_code(code).unwrap() {
+        Attribute::Code {
+            code, max_stack, ..
+        } => {
+            assert_eq!(code, vec![0xb2, 0x00, 0x06, 0xb3, 0x00, 0x06, 0xb1]);
+            assert_eq!(max_stack, 2);
+        }
+        _ => unreachable!(),
+    }
+}
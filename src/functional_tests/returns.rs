@@ -0,0 +1,96 @@
+use crate::instruction::*;
+
+use super::*;
+
+fn returning(name: &str, ty: &str, code: CodeBuilder) -> MethodBuilder {
+    MethodBuilder::new()
+        .access_flag(AccessFlag::Public)
+        .access_flag(AccessFlag::Static)
+        .name(name)
+        .r#return(ty)
+        .code(code)
+}
+
+#[test]
+fn typed_returns() {
+    let main = CodeBuilder::new().instructions([
+        invokestatic("Test", "long", "()J"),
+        invokestatic("java/lang/Long", "valueOf", "(J)Ljava/lang/Long;"),
+        print(),
+        invokestatic("Test", "float", "()F"),
+        invokestatic("java/lang/Float", "valueOf", "(F)Ljava/lang/Float;"),
+        print(),
+        invokestatic("Test", "double", "()D"),
+        invokestatic("java/lang/Double", "valueOf", "(D)Ljava/lang/Double;"),
+        print(),
+        invokestatic("Test", "string", "()Ljava/lang/String;"),
+        print(),
+        invokestatic("Test", "bool", "()Z"),
+        invokestatic("java/lang/Boolean", "valueOf", "(Z)Ljava/lang/Boolean;"),
+        print(),
+        r#return(),
+    ]);
+
+    let class = test_class(main)
+        .method(returning(
+            "long",
+            "J",
+            CodeBuilder::new().instructions([push_long(1 << 33), lreturn()]),
+        ))
+        .method(returning(
+            "float",
+            "F",
+            CodeBuilder::new().instructions([push_float(0.25), freturn()]),
+        ))
+        .method(returning(
+            "double",
+            "D",
+            CodeBuilder::new().instructions([push_double(0.75), dreturn()]),
+        ))
+        .method(returning(
+            "string",
+            "Ljava/lang/String;",
+            CodeBuilder::new().instructions([ldc("returned"), return_value("Ljava/lang/String;")]),
+        ))
+        .method(returning(
+            "bool",
+            "Z",
+            CodeBuilder::new().instructions([iconst(1), return_value("Z")]),
+        ));
+
+    run_bytecode(
+        class.emit().unwrap(),
+        "8589934592\n0.25\n0.75\nreturned\ntrue\n",
+    );
+}
+
+#[test]
+fn return_instructions_must_match_the_return_type() {
+    let emit = |ty: &str, code: CodeBuilder| {
+        ClassFileBuilder::new()
+            .class_name("Test")
+            .method(returning("test", ty, code))
+            .emit()
+    };
+
+    assert!(matches!(
+        emit("V", CodeBuilder::new().instructions([iconst(0), ireturn()])),
+        Err(Error::ReturnTypeMismatch(instruction, ty))
+            if *instruction == Instruction::ireturn && ty == "V"
+    ));
+    assert!(matches!(
+        emit("I", CodeBuilder::new().instructions([r#return()])),
+        Err(Error::ReturnTypeMismatch(instruction, ty))
+            if *instruction == Instruction::r#return && ty == "I"
+    ));
+    assert!(matches!(
+        emit("[I", CodeBuilder::new().instructions([push_long(0), lreturn()])),
+        Err(Error::ReturnTypeMismatch(instruction, ty))
+            if *instruction == Instruction::lreturn && ty == "[I"
+    ));
+    assert!(emit(
+        "[I",
+        CodeBuilder::new().instructions([iconst(0), newarray(ArrayType::Int), areturn()])
+    )
+    .is_ok());
+}
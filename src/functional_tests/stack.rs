@@ -0,0 +1,138 @@
+use crate::instruction::*;
+
+use super::*;
+
+#[test]
+fn single_slot_stack_manipulation() {
+    let main = CodeBuilder::new()
+        .instructions([
+            new("java/lang/StringBuilder"),
+            dup(),
+            ldc("constructed"),
+            invokespecial("java/lang/StringBuilder", "<init>", "(Ljava/lang/String;)V"),
+            print(),
+            nop(),
+            // 2 - 1
+            iconst(1),
+            iconst(2),
+            swap(),
+            isub(),
+        ])
+        .instructions(print_int())
+        // 4, 3, 4 -> 4 + (3 - 4)
+        .instructions([iconst(3), iconst(4), dup_x1(), isub(), iadd()])
+        .instructions(print_int())
+        // 3, 1, 2, 3 -> 3 * (1 + 2 + 3)
+        .instructions([
+            iconst(1),
+            iconst(2),
+            iconst(3),
+            dup_x2(),
+            iadd(),
+            iadd(),
+            imul(),
+        ])
+        .instructions(print_int())
+        // 5, 6, 5, 6 -> 5 + 6 + 5 + 6
+        .instructions([
+            iconst(5),
+            iconst(2),
+            pop(),
+            push_int(6),
+            dup2(),
+            iadd(),
+            iadd(),
+            iadd(),
+        ])
+        .instructions(print_int())
+        // 2, 3, 1, 2, 3 -> 2 + 3 + 1 + 2 + 3
+        .instructions([
+            iconst(1),
+            iconst(2),
+            iconst(3),
+            dup2_x1(),
+            iadd(),
+            iadd(),
+            iadd(),
+            iadd(),
+            iconst(5),
+            iconst(5),
+            pop2(),
+        ])
+        .instructions(print_int())
+        .instructions([r#return()]);
+
+    run_bytecode(
+        test_class(main).emit().unwrap(),
+        "constructed\n1\n3\n18\n22\n11\n",
+    );
+}
+
+#[test]
+fn two_slot_values_use_category_aware_helpers() {
+    let main = CodeBuilder::new()
+        // 5 + 5
+        .instructions([push_long(5), dup_value("J"), ladd()])
+        .instructions(print_long())
+        // 2, 10, 2 -> 2 + 10
+        .instructions([push_long(10), iconst(2), dup_x2(), pop(), l2i(), iadd()])
+        .instructions(print_int())
+        // 7, 1, 7 -> 7
+        .instructions([
+            iconst(1),
+            push_long(7),
+            dup_value_x1("J"),
+            pop_value("J"),
+            pop_value("I"),
+        ])
+        .instructions(print_long())
+        // 4, 3, 4 -> 4 * (3 - 4)
+        .instructions([
+            push_long(3),
+            push_long(4),
+            dup_value_x2("J"),
+            lsub(),
+            lmul(),
+        ])
+        .instructions(print_long())
+        // 3, 4, 1, 2, 3, 4 -> 3 + 4 + 1 + 2 + 3 + 4
+        .instructions([
+            iconst(1),
+            iconst(2),
+            iconst(3),
+            iconst(4),
+            dup2_x2(),
+            iadd(),
+            iadd(),
+            iadd(),
+            iadd(),
+            iadd(),
+        ])
+        .instructions(print_int())
+        .instructions([r#return()]);
+
+    run_bytecode(test_class(main).emit().unwrap(), "10\n12\n7\n-4\n17\n");
+}
+
+#[test]
+fn wide_values_cannot_be_split() {
+    assert!(matches!(
+        build_code(CodeBuilder::new().instructions([push_long(1), iconst(1), swap(), r#return()])),
+        Err(Error::StackTypeMismatch(_))
+    ));
+    assert!(matches!(
+        build_code(CodeBuilder::new().instructions([push_long(1), dup(), r#return()])),
+        Err(Error::StackTypeMismatch(_))
+    ));
+    assert!(matches!(
+        build_code(CodeBuilder::new().instructions([
+            iconst(1),
+            push_long(1),
+            dup2_x1(),
+            pop2(),
+            pop2(),
+            r#return()
+        ])),
+        Err(Error::StackTypeMismatch(_))
+    ));
+}
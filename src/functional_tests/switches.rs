@@ -0,0 +1,207 @@
+use crate::{attribute::try_builder::TryBuilder, instruction::*, label::Label};
+
+use super::*;
+
+/// Switches on each of `values` in turn, printing the name of the case taken.
+fn print_cases(main: &mut CodeBuilder, values: &[i32], cases: &[i32]) -> Vec<Instruction> {
+    let mut instructions = Vec::new();
+    for value in values {
+        let targets: Vec<_> = cases.iter().map(|_| main.new_label()).collect();
+        let default = main.new_label();
+        let done = main.new_label();
+
+        instructions.push(push_int(*value));
+        instructions.push(switch(
+            cases.iter().copied().zip(targets.iter().copied()),
+            default,
+        ));
+        for (case, target) in cases.iter().zip(targets) {
+            instructions.push(label(target));
+            instructions.push(ldc(format!("case {}", case)));
+            instructions.push(goto(done));
+        }
+        instructions.push(label(default));
+        instructions.push(ldc("default"));
+        instructions.push(label(done));
+        instructions.push(print());
+    }
+    instructions
+}
+
+#[test]
+fn switch_operands_are_aligned() {
+    for prefix in 0..4 {
+        let mut code = CodeBuilder::new();
+        let case = code.new_label();
+        let default = code.new_label();
+        let code = code
+            .instructions(std::iter::repeat_n(nop(), prefix))
+            .instructions([
+                iload(0),
+                tableswitch(7, [case], default),
+                label(case),
+                label(default),
+                r#return(),
+            ]);
+
+        let bytes = code_bytes(code);
+        let start = prefix + 1;
+        let padding = (4 - (start + 1) % 4) % 4;
+        let size = (1 + padding + 16) as i32;
+
+        let mut expected = vec![0xaa];
+        expected.extend(std::iter::repeat_n(0, padding));
+        expected.extend_from_slice(&size.to_be_bytes());
+        expected.extend_from_slice(&7i32.to_be_bytes());
+        expected.extend_from_slice(&7i32.to_be_bytes());
+        expected.extend_from_slice(&size.to_be_bytes());
+        assert_eq!(&bytes[start..start + size as usize], expected.as_slice());
+        assert_eq!((start + 1 + padding) % 4, 0);
+    }
+}
+
+#[test]
+fn lookupswitch_keys_are_sorted() {
+    let mut code = CodeBuilder::new();
+    let (a, b, default) = (code.new_label(), code.new_label(), code.new_label());
+    let code = code.instructions([
+        iload(0),
+        lookupswitch([(5, a), (-3, b)], default),
+        label(a),
+        label(b),
+        label(default),
+        r#return(),
+    ]);
+
+    let bytes = code_bytes(code);
+    let mut expected = vec![0xab, 0, 0];
+    for value in [27i32, 2, -3, 27, 5, 27] {
+        expected.extend_from_slice(&value.to_be_bytes());
+    }
+    assert_eq!(&bytes[1..28], expected.as_slice());
+}
+
+#[test]
+fn duplicate_lookupswitch_case() {
+    let mut code = CodeBuilder::new();
+    let (a, default) = (code.new_label(), code.new_label());
+    let code = code.instructions([
+        iload(0),
+        lookupswitch([(1, a), (1, default)], default),
+        label(a),
+        label(default),
+        r#return(),
+    ]);
+
+    assert!(matches!(
+        build_code(code),
+        Err(Error::DuplicateSwitchCase(1))
+    ));
+}
+
+#[test]
+fn switch_chooses_by_density() {
+    let labels: Vec<_> = (0..5).map(Label).collect();
+    let default = labels[4];
+
+    let dense = switch(
+        [
+            (5, labels[3]),
+            (1, labels[0]),
+            (2, labels[1]),
+            (3, labels[2]),
+        ],
+        default,
+    );
+    assert_eq!(
+        dense,
+        tableswitch(
+            1,
+            [labels[0], labels[1], labels[2], default, labels[3]],
+            default
+        )
+    );
+
+    let sparse = switch([(1, labels[0]), (3, labels[1])], default);
+    assert!(matches!(sparse, Instruction::lookupswitch { .. }));
+
+    let wide = switch(
+        [(i32::MIN, labels[0]), (0, labels[1]), (i32::MAX, labels[2])],
+        default,
+    );
+    assert!(matches!(wide, Instruction::lookupswitch { .. }));
+
+    let empty = switch([], default);
+    assert_eq!(empty, lookupswitch([], default));
+}
+
+#[test]
+fn run_switches() {
+    let mut main = CodeBuilder::new();
+    let table = print_cases(&mut main, &[0, 1, 2, 4], &[1, 2, 3]);
+    let lookup = print_cases(&mut main, &[-1000, 7, 1000000], &[1000000, -1000, 0]);
+
+    let main = main
+        .instructions(table)
+        .instructions(lookup)
+        .instructions([r#return()]);
+
+    run_bytecode(
+        test_class(main).emit().unwrap(),
+        "default\ncase 1\ncase 2\ndefault\ncase -1000\ndefault\ncase 1000000\n",
+    );
+}
+
+#[test]
+fn switch_out_of_try_runs_finally() {
+    let mut main = CodeBuilder::new();
+    let (inside, outside, after) = (main.new_label(), main.new_label(), main.new_label());
+    let main = main
+        .try_block(
+            TryBuilder::new()
+                .body([
+                    iconst(2),
+                    tableswitch(1, [inside, outside], inside),
+                    label(inside),
+                    ldc("inside"),
+                    print(),
+                ])
+                .finally([ldc("finally"), print()]),
+        )
+        .instructions([
+            goto(after),
+            label(outside),
+            ldc("outside"),
+            print(),
+            label(after),
+            r#return(),
+        ]);
+
+    run_bytecode(test_class(main).emit().unwrap(), "finally\noutside\n");
+}
+
+#[test]
+fn string_switch() {
+    let mut main = CodeBuilder::new();
+    for value in ["BB", "Aa", "C", "nope", "héllo", "Aa"] {
+        let cases: Vec<_> = ["Aa", "BB", "C", "héllo", "C"]
+            .into_iter()
+            .map(|key| (key, main.new_label()))
+            .collect();
+        let default = main.new_label();
+        let done = main.new_label();
+
+        main = main
+            .instructions([ldc(value)])
+            .string_switch(cases.clone(), default);
+        for (index, (_, target)) in cases.into_iter().enumerate() {
+            main = main.instructions([label(target), ldc(format!("case {}", index)), goto(done)]);
+        }
+        main = main.instructions([label(default), ldc("default"), label(done), print()]);
+    }
+
+    run_bytecode(
+        test_class(main.instructions([r#return()])).emit().unwrap(),
+        "case 1\ncase 0\ncase 2\ndefault\ncase 3\ncase 0\n",
+    );
+}
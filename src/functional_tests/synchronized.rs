@@ -0,0 +1,152 @@
+use crate::{
+    attribute::{try_builder::TryBuilder, ExceptionTableEntry},
+    instruction::*,
+    local::Local,
+};
+
+use super::*;
+
+/// Prints whether the current thread holds the monitor of `lock`.
+fn print_holds_lock(lock: Local) -> [Instruction; 4] {
+    [
+        aload(lock),
+        invokestatic("java/lang/Thread", "holdsLock", "(Ljava/lang/Object;)Z"),
+        invokestatic("java/lang/Boolean", "valueOf", "(Z)Ljava/lang/Boolean;"),
+        print(),
+    ]
+}
+
+/// Creates a new local holding a new `Object` to lock on.
+fn new_lock(code: &mut CodeBuilder) -> (Local, [Instruction; 4]) {
+    let lock = code.new_local("Ljava/lang/Object;");
+    (
+        lock,
+        [
+            new("java/lang/Object"),
+            dup(),
+            invokespecial("java/lang/Object", "<init>", "()V"),
+            astore(lock),
+        ],
+    )
+}
+
+#[test]
+fn synchronized_block_holds_monitor() {
+    let mut main = CodeBuilder::new();
+    let (lock, create) = new_lock(&mut main);
+    let main = main
+        .instructions(create)
+        .synchronized_block([aload(lock)], print_holds_lock(lock))
+        .instructions(print_holds_lock(lock))
+        .instructions([r#return()]);
+
+    run_bytecode(test_class(main).emit().unwrap(), "true\nfalse\n");
+}
+
+#[test]
+fn synchronized_block_releases_monitor_on_exception() {
+    let mut main = CodeBuilder::new();
+    let (lock, create) = new_lock(&mut main);
+    let main = main
+        .instructions(create)
+        .try_block(
+            TryBuilder::new()
+                .body([aload(lock)])
+                .body([invokestatic("Test", "fail", "(Ljava/lang/Object;)V")])
+                .catch("java/lang/ArithmeticException", [print()]),
+        )
+        .instructions(print_holds_lock(lock))
+        .instructions([r#return()]);
+
+    let fail = CodeBuilder::new()
+        .synchronized_block(
+            [aload(0)],
+            [
+                iconst(1),
+                iconst(0),
+                idiv(),
+                invokestatic("java/lang/Integer", "valueOf", "(I)Ljava/lang/Integer;"),
+                print(),
+            ],
+        )
+        .instructions([r#return()]);
+
+    let bytes = test_class(main)
+        .method(
+            MethodBuilder::new()
+                .access_flag(AccessFlag::Static)
+                .name("fail")
+                .parameter("Ljava/lang/Object;")
+                .code(fail),
+        )
+        .emit()
+        .unwrap();
+
+    run_bytecode(bytes, "java.lang.ArithmeticException: / by zero\nfalse\n");
+}
+
+#[test]
+fn synchronized_block_releases_monitor_on_return() {
+    let mut main = CodeBuilder::new();
+    let (lock, create) = new_lock(&mut main);
+    let main = main
+        .instructions(create)
+        .instructions([
+            aload(lock),
+            invokestatic("Test", "value", "(Ljava/lang/Object;)I"),
+            invokestatic("java/lang/Integer", "valueOf", "(I)Ljava/lang/Integer;"),
+            print(),
+        ])
+        .instructions(print_holds_lock(lock))
+        .instructions([r#return()]);
+
+    let value = CodeBuilder::new().synchronized_block([aload(0)], [iconst(1), ireturn()]);
+
+    let bytes = test_class(main)
+        .method(
+            MethodBuilder::new()
+                .access_flag(AccessFlag::Static)
+                .name("value")
+                .parameter("Ljava/lang/Object;")
+                .r#return("I")
+                .code(value),
+        )
+        .emit()
+        .unwrap();
+
+    run_bytecode(bytes, "1\nfalse\n");
+}
+
+#[test]
+fn synchronized_block_handler_protects_itself() {
+    let code = CodeBuilder::new()
+        .synchronized_block([ldc("lock")], [nop()])
+        .instructions([r#return()]);
+
+    let exception_table = match build_code(code).unwrap() {
+        Attribute::Code {
+            exception_table, ..
+        } => exception_table,
+        _ => unreachable!(),
+    };
+
+    // 0: ldc, dup, astore_1, monitorenter, nop, aload_1, monitorexit, goto
+    // 11: astore_2, aload_1, monitorexit, aload_2, athrow
+    assert_eq!(
+        exception_table,
+        [
+            ExceptionTableEntry {
+                start_pc: 5,
+                end_pc: 8,
+                handler_pc: 11,
+                catch_type: None,
+            },
+            ExceptionTableEntry {
+                start_pc: 11,
+                end_pc: 14,
+                handler_pc: 11,
+                catch_type: None,
+            },
+        ]
+    );
+}
@@ -1,57 +1,815 @@
-use crate::{constant_pool::ConstantPool, Error};
+use std::collections::HashMap;
+
+use crate::{
+    constant_pool::{BootstrapMethod, Constant, ConstantPool},
+    descriptor::{slot_size, MethodDescriptor},
+    label::Label,
+    local::{LocalIndex, LocalType},
+    Error,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The element type of an array created by the `newarray` instruction.
+pub enum ArrayType {
+    /// `T_BOOLEAN`
+    Boolean = 4,
+    /// `T_CHAR`
+    Char = 5,
+    /// `T_FLOAT`
+    Float = 6,
+    /// `T_DOUBLE`
+    Double = 7,
+    /// `T_BYTE`
+    Byte = 8,
+    /// `T_SHORT`
+    Short = 9,
+    /// `T_INT`
+    Int = 10,
+    /// `T_LONG`
+    Long = 11,
+}
+
+impl ArrayType {
+    /// Returns the descriptor of an array with this element type.
+    pub fn descriptor(&self) -> &'static str {
+        match self {
+            ArrayType::Boolean => "[Z",
+            ArrayType::Char => "[C",
+            ArrayType::Float => "[F",
+            ArrayType::Double => "[D",
+            ArrayType::Byte => "[B",
+            ArrayType::Short => "[S",
+            ArrayType::Int => "[I",
+            ArrayType::Long => "[J",
+        }
+    }
+}
 
 #[allow(non_camel_case_types)]
 #[derive(Debug, Clone, PartialEq, Eq)]
 /// Represents a JVM bytecode instruction.
 /// See https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5
 pub enum Instruction {
+    /// Marks the position of a [Label] in the instruction stream. This emits no bytes.
+    label {
+        label: Label,
+    },
     aload {
-        index: u8,
+        index: LocalIndex,
     },
     iconst {
         value: i32,
     },
+    lconst_0,
+    lconst_1,
+    fconst_0,
+    fconst_1,
+    fconst_2,
+    dconst_0,
+    dconst_1,
+    bipush {
+        value: i8,
+    },
+    sipush {
+        value: i16,
+    },
+    /// Pushes a single-slot constant whose constant pool index fits in a byte.
+    /// [crate::attribute::code::CodeBuilder] replaces this with `ldc_w` when it does not.
+    ldc {
+        constant: Constant,
+    },
+    ldc_w {
+        constant: Constant,
+    },
+    ldc2_w {
+        constant: Constant,
+    },
     iload {
-        index: u8,
+        index: LocalIndex,
+    },
+    lload {
+        index: LocalIndex,
+    },
+    fload {
+        index: LocalIndex,
+    },
+    dload {
+        index: LocalIndex,
+    },
+    istore {
+        index: LocalIndex,
+    },
+    lstore {
+        index: LocalIndex,
+    },
+    fstore {
+        index: LocalIndex,
+    },
+    dstore {
+        index: LocalIndex,
+    },
+    astore {
+        index: LocalIndex,
     },
     iadd,
+    ladd,
+    fadd,
+    dadd,
+    isub,
+    lsub,
+    fsub,
+    dsub,
+    imul,
+    lmul,
+    fmul,
+    dmul,
+    idiv,
+    ldiv,
+    fdiv,
+    ddiv,
+    irem,
+    lrem,
+    frem,
+    drem,
+    ineg,
+    lneg,
+    fneg,
+    dneg,
+    ishl,
+    lshl,
+    ishr,
+    lshr,
+    iushr,
+    lushr,
+    iand,
+    land,
+    ior,
+    lor,
+    ixor,
+    lxor,
+    /// Increments the `int` local variable at `index` by `value`.
+    iinc {
+        index: LocalIndex,
+        value: i16,
+    },
+    i2l,
+    i2f,
+    i2d,
+    l2i,
+    l2f,
+    l2d,
+    f2i,
+    f2l,
+    f2d,
+    d2i,
+    d2l,
+    d2f,
+    i2b,
+    i2c,
+    i2s,
+    lcmp,
+    /// Compares two floats, pushing -1 if either is NaN.
+    fcmpl,
+    /// Compares two floats, pushing 1 if either is NaN.
+    fcmpg,
+    /// Compares two doubles, pushing -1 if either is NaN.
+    dcmpl,
+    /// Compares two doubles, pushing 1 if either is NaN.
+    dcmpg,
+    ifeq {
+        target: Label,
+    },
+    ifne {
+        target: Label,
+    },
+    iflt {
+        target: Label,
+    },
+    ifge {
+        target: Label,
+    },
+    ifgt {
+        target: Label,
+    },
+    ifle {
+        target: Label,
+    },
+    if_icmpeq {
+        target: Label,
+    },
+    if_icmpne {
+        target: Label,
+    },
+    if_icmplt {
+        target: Label,
+    },
+    if_icmpge {
+        target: Label,
+    },
+    if_icmpgt {
+        target: Label,
+    },
+    if_icmple {
+        target: Label,
+    },
+    if_acmpeq {
+        target: Label,
+    },
+    if_acmpne {
+        target: Label,
+    },
+    goto {
+        target: Label,
+    },
+    jsr {
+        target: Label,
+    },
+    ifnull {
+        target: Label,
+    },
+    ifnonnull {
+        target: Label,
+    },
+    goto_w {
+        target: Label,
+    },
+    jsr_w {
+        target: Label,
+    },
+    /// Jumps to `targets[value - low]`, or to `default` if the value is out of range.
+    tableswitch {
+        low: i32,
+        targets: Vec<Label>,
+        default: Label,
+    },
+    /// Jumps to the label paired with the value, or to `default` if there is none.
+    lookupswitch {
+        cases: Vec<(i32, Label)>,
+        default: Label,
+    },
+    /// Calls a constructor, private method or super class method. `is_interface` must be set if
+    /// `class` is an interface.
     invokespecial {
         class: String,
         name: String,
         descriptor: String,
+        is_interface: bool,
     },
     invokevirtual {
         class: String,
         name: String,
         descriptor: String,
     },
+    /// Calls a static method. `is_interface` must be set if `class` is an interface.
     invokestatic {
         class: String,
         name: String,
         descriptor: String,
+        is_interface: bool,
+    },
+    invokeinterface {
+        class: String,
+        name: String,
+        descriptor: String,
+    },
+    /// Calls the method linked to this call site by `bootstrap_method`.
+    invokedynamic {
+        name: String,
+        descriptor: String,
+        bootstrap_method: BootstrapMethod,
     },
     getstatic {
         class: String,
         name: String,
         descriptor: String,
     },
+    putstatic {
+        class: String,
+        name: String,
+        descriptor: String,
+    },
+    getfield {
+        class: String,
+        name: String,
+        descriptor: String,
+    },
+    putfield {
+        class: String,
+        name: String,
+        descriptor: String,
+    },
+    /// Creates an uninitialized instance of `class`, which must be initialized by calling one of
+    /// its constructors with `invokespecial`.
+    new {
+        class: String,
+    },
+    /// Checks that a reference is an instance of `class`, which may be an array descriptor.
+    checkcast {
+        class: String,
+    },
+    /// Pushes whether a reference is an instance of `class`, which may be an array descriptor.
+    instanceof {
+        class: String,
+    },
+    arraylength,
+    /// Creates a new array of primitive values.
+    newarray {
+        ty: ArrayType,
+    },
+    /// Creates a new array of references to `class`, which may be an array descriptor.
+    anewarray {
+        class: String,
+    },
+    /// Creates a new multidimensional array of type `class`, an array descriptor, with the
+    /// lengths of the first `dimensions` dimensions taken from the stack.
+    /// `dimensions` must be at least 1, and at most the number of dimensions of `class`.
+    multianewarray {
+        class: String,
+        dimensions: u8,
+    },
+    iaload,
+    laload,
+    faload,
+    daload,
+    aaload,
+    baload,
+    caload,
+    saload,
+    iastore,
+    lastore,
+    fastore,
+    dastore,
+    aastore,
+    bastore,
+    castore,
+    sastore,
+    nop,
+    pop,
+    /// Pops a `long` or `double`, or two other values.
+    pop2,
+    dup,
+    /// Duplicates the top value and inserts it beneath the value below it.
+    dup_x1,
+    /// Duplicates the top value and inserts it beneath the two slots below it.
+    dup_x2,
+    /// Duplicates a `long` or `double`, or the top two other values.
+    dup2,
+    /// Duplicates the top two slots and inserts them beneath the value below them.
+    dup2_x1,
+    /// Duplicates the top two slots and inserts them beneath the two slots below them.
+    dup2_x2,
+    /// Swaps the top two values, neither of which can be a `long` or `double`.
+    swap,
     ireturn,
+    lreturn,
+    freturn,
+    dreturn,
+    areturn,
     r#return,
+    athrow,
+    /// Acquires the monitor of the object on top of the stack.
+    monitorenter,
+    /// Releases the monitor of the object on top of the stack.
+    monitorexit,
 }
 
 impl Instruction {
+    /// Returns the size of the instruction in bytes, when placed at `offset` from the start of the
+    /// method.
+    pub fn size(&self, offset: u32) -> u32 {
+        match self {
+            Instruction::tableswitch { targets, .. } => {
+                1 + switch_padding(offset) + 12 + 4 * targets.len() as u32
+            }
+            Instruction::lookupswitch { cases, .. } => {
+                1 + switch_padding(offset) + 8 + 8 * cases.len() as u32
+            }
+            Instruction::label { .. } => 0,
+            Instruction::iinc {
+                index: LocalIndex::Slot(0..=255),
+                value: -128..=127,
+            } => 3,
+            Instruction::iinc { .. } => 6,
+            _ if self.local_access().is_some() => {
+                let (_, _, _, index) = self.local_access().unwrap();
+                match index {
+                    LocalIndex::Slot(0..=3) => 1,
+                    LocalIndex::Slot(0..=255) => 2,
+                    _ => 4,
+                }
+            }
+            Instruction::bipush { .. } | Instruction::ldc { .. } | Instruction::newarray { .. } => 2,
+            Instruction::sipush { .. }
+            | Instruction::ldc_w { .. }
+            | Instruction::ldc2_w { .. }
+            | Instruction::invokespecial { .. }
+            | Instruction::invokestatic { .. }
+            | Instruction::invokevirtual { .. }
+            | Instruction::getstatic { .. }
+            | Instruction::putstatic { .. }
+            | Instruction::getfield { .. }
+            | Instruction::putfield { .. }
+            | Instruction::new { .. }
+            | Instruction::checkcast { .. }
+            | Instruction::instanceof { .. }
+            | Instruction::anewarray { .. } => 3,
+            Instruction::multianewarray { .. } => 4,
+            Instruction::goto_w { .. }
+            | Instruction::jsr_w { .. }
+            | Instruction::invokeinterface { .. }
+            | Instruction::invokedynamic { .. } => 5,
+            _ if self.branch_target().is_some() => 3,
+            _ => 1,
+        }
+    }
+
+    /// Returns the label this instruction branches to, if it is a branch instruction.
+    pub fn branch_target(&self) -> Option<Label> {
+        match self {
+            Instruction::ifeq { target }
+            | Instruction::ifne { target }
+            | Instruction::iflt { target }
+            | Instruction::ifge { target }
+            | Instruction::ifgt { target }
+            | Instruction::ifle { target }
+            | Instruction::if_icmpeq { target }
+            | Instruction::if_icmpne { target }
+            | Instruction::if_icmplt { target }
+            | Instruction::if_icmpge { target }
+            | Instruction::if_icmpgt { target }
+            | Instruction::if_icmple { target }
+            | Instruction::if_acmpeq { target }
+            | Instruction::if_acmpne { target }
+            | Instruction::goto { target }
+            | Instruction::jsr { target }
+            | Instruction::ifnull { target }
+            | Instruction::ifnonnull { target }
+            | Instruction::goto_w { target }
+            | Instruction::jsr_w { target } => Some(*target),
+            _ => None,
+        }
+    }
+
+    /// Returns every label this instruction can branch to, including all targets of a switch.
+    pub fn branch_targets(&self) -> Vec<Label> {
+        match self {
+            Instruction::tableswitch {
+                targets, default, ..
+            } => targets.iter().copied().chain([*default]).collect(),
+            Instruction::lookupswitch { cases, default } => cases
+                .iter()
+                .map(|(_, target)| *target)
+                .chain([*default])
+                .collect(),
+            _ => self.branch_target().into_iter().collect(),
+        }
+    }
+
+    /// Returns every label this instruction places or refers to.
+    pub(crate) fn labels_mut(&mut self) -> Vec<&mut Label> {
+        match self {
+            Instruction::label { label } => vec![label],
+            Instruction::ifeq { target }
+            | Instruction::ifne { target }
+            | Instruction::iflt { target }
+            | Instruction::ifge { target }
+            | Instruction::ifgt { target }
+            | Instruction::ifle { target }
+            | Instruction::if_icmpeq { target }
+            | Instruction::if_icmpne { target }
+            | Instruction::if_icmplt { target }
+            | Instruction::if_icmpge { target }
+            | Instruction::if_icmpgt { target }
+            | Instruction::if_icmple { target }
+            | Instruction::if_acmpeq { target }
+            | Instruction::if_acmpne { target }
+            | Instruction::goto { target }
+            | Instruction::jsr { target }
+            | Instruction::ifnull { target }
+            | Instruction::ifnonnull { target }
+            | Instruction::goto_w { target }
+            | Instruction::jsr_w { target } => vec![target],
+            Instruction::tableswitch {
+                targets, default, ..
+            } => targets.iter_mut().chain([default]).collect(),
+            Instruction::lookupswitch { cases, default } => cases
+                .iter_mut()
+                .map(|(_, target)| target)
+                .chain([default])
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the number of local variable slots needed for this instruction to access its local
+    /// variable, or 0 if it does not access one.
+    pub fn locals_used(&self) -> u16 {
+        match (self, self.local_access()) {
+            (_, Some((_, _, size, LocalIndex::Slot(index)))) => index.saturating_add(size),
+            (
+                Instruction::iinc {
+                    index: LocalIndex::Slot(index),
+                    ..
+                },
+                _,
+            ) => index.saturating_add(1),
+            _ => 0,
+        }
+    }
+
+    /// For load and store instructions, returns the opcode of the general form of the instruction,
+    /// the opcode of its `_0` form, the number of slots taken by the local, and the local accessed.
+    fn local_access(&self) -> Option<(u8, u8, u16, &LocalIndex)> {
+        Some(match self {
+            Instruction::iload { index } => (0x15, 0x1a, 1, index),
+            Instruction::lload { index } => (0x16, 0x1e, 2, index),
+            Instruction::fload { index } => (0x17, 0x22, 1, index),
+            Instruction::dload { index } => (0x18, 0x26, 2, index),
+            Instruction::aload { index } => (0x19, 0x2a, 1, index),
+            Instruction::istore { index } => (0x36, 0x3b, 1, index),
+            Instruction::lstore { index } => (0x37, 0x3f, 2, index),
+            Instruction::fstore { index } => (0x38, 0x43, 1, index),
+            Instruction::dstore { index } => (0x39, 0x47, 2, index),
+            Instruction::astore { index } => (0x3a, 0x4b, 1, index),
+            _ => return None,
+        })
+    }
+
+    /// Returns the local variable accessed by this instruction, if any.
+    pub(crate) fn local_index_mut(&mut self) -> Option<&mut LocalIndex> {
+        match self {
+            Instruction::iload { index }
+            | Instruction::lload { index }
+            | Instruction::fload { index }
+            | Instruction::dload { index }
+            | Instruction::aload { index }
+            | Instruction::istore { index }
+            | Instruction::lstore { index }
+            | Instruction::fstore { index }
+            | Instruction::dstore { index }
+            | Instruction::astore { index }
+            | Instruction::iinc { index, .. } => Some(index),
+            _ => None,
+        }
+    }
+
+    /// Returns the type of local variable accessed by this instruction, if it accesses one.
+    pub(crate) fn local_type(&self) -> Option<LocalType> {
+        match self {
+            Instruction::iload { .. } | Instruction::istore { .. } | Instruction::iinc { .. } => {
+                Some(LocalType::Int)
+            }
+            Instruction::lload { .. } | Instruction::lstore { .. } => Some(LocalType::Long),
+            Instruction::fload { .. } | Instruction::fstore { .. } => Some(LocalType::Float),
+            Instruction::dload { .. } | Instruction::dstore { .. } => Some(LocalType::Double),
+            Instruction::aload { .. } | Instruction::astore { .. } => Some(LocalType::Reference),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of operand stack slots popped and then pushed by this instruction.
+    /// `long` and `double` values take two slots.
+    pub fn stack_effect(&self) -> Result<(u16, u16), Error> {
+        Ok(match self {
+            Instruction::label { .. } => (0, 0),
+            Instruction::aload { .. }
+            | Instruction::iload { .. }
+            | Instruction::fload { .. }
+            | Instruction::iconst { .. }
+            | Instruction::fconst_0
+            | Instruction::fconst_1
+            | Instruction::fconst_2
+            | Instruction::bipush { .. }
+            | Instruction::sipush { .. }
+            | Instruction::ldc { .. }
+            | Instruction::ldc_w { .. } => (0, 1),
+            Instruction::lconst_0
+            | Instruction::lconst_1
+            | Instruction::dconst_0
+            | Instruction::dconst_1
+            | Instruction::ldc2_w { .. } => (0, 2),
+            Instruction::lload { .. } | Instruction::dload { .. } => (0, 2),
+            Instruction::istore { .. } | Instruction::fstore { .. } | Instruction::astore { .. } => {
+                (1, 0)
+            }
+            Instruction::lstore { .. } | Instruction::dstore { .. } => (2, 0),
+            Instruction::iadd
+            | Instruction::isub
+            | Instruction::imul
+            | Instruction::idiv
+            | Instruction::irem
+            | Instruction::ishl
+            | Instruction::ishr
+            | Instruction::iushr
+            | Instruction::iand
+            | Instruction::ior
+            | Instruction::ixor
+            | Instruction::fadd
+            | Instruction::fsub
+            | Instruction::fmul
+            | Instruction::fdiv
+            | Instruction::frem => (2, 1),
+            Instruction::ladd
+            | Instruction::lsub
+            | Instruction::lmul
+            | Instruction::ldiv
+            | Instruction::lrem
+            | Instruction::land
+            | Instruction::lor
+            | Instruction::lxor
+            | Instruction::dadd
+            | Instruction::dsub
+            | Instruction::dmul
+            | Instruction::ddiv
+            | Instruction::drem => (4, 2),
+            Instruction::lshl | Instruction::lshr | Instruction::lushr => (3, 2),
+            Instruction::ineg | Instruction::fneg => (1, 1),
+            Instruction::lneg | Instruction::dneg => (2, 2),
+            Instruction::iinc { .. } => (0, 0),
+            Instruction::i2l | Instruction::i2d | Instruction::f2l | Instruction::f2d => (1, 2),
+            Instruction::i2f
+            | Instruction::f2i
+            | Instruction::i2b
+            | Instruction::i2c
+            | Instruction::i2s => (1, 1),
+            Instruction::l2i
+            | Instruction::l2f
+            | Instruction::d2i
+            | Instruction::d2f
+            | Instruction::fcmpl
+            | Instruction::fcmpg => (2, 1),
+            Instruction::l2d | Instruction::d2l => (2, 2),
+            Instruction::lcmp | Instruction::dcmpl | Instruction::dcmpg => (4, 1),
+            Instruction::ifeq { .. }
+            | Instruction::ifne { .. }
+            | Instruction::iflt { .. }
+            | Instruction::ifge { .. }
+            | Instruction::ifgt { .. }
+            | Instruction::ifle { .. }
+            | Instruction::ifnull { .. }
+            | Instruction::ifnonnull { .. }
+            | Instruction::tableswitch { .. }
+            | Instruction::lookupswitch { .. } => (1, 0),
+            Instruction::if_icmpeq { .. }
+            | Instruction::if_icmpne { .. }
+            | Instruction::if_icmplt { .. }
+            | Instruction::if_icmpge { .. }
+            | Instruction::if_icmpgt { .. }
+            | Instruction::if_icmple { .. }
+            | Instruction::if_acmpeq { .. }
+            | Instruction::if_acmpne { .. } => (2, 0),
+            Instruction::goto { .. } | Instruction::goto_w { .. } => (0, 0),
+            Instruction::jsr { .. } | Instruction::jsr_w { .. } => (0, 1),
+            Instruction::invokespecial { descriptor, .. }
+            | Instruction::invokevirtual { descriptor, .. }
+            | Instruction::invokeinterface { descriptor, .. } => {
+                let descriptor = MethodDescriptor::parse(descriptor)?;
+                (
+                    descriptor.parameter_slots() + 1,
+                    slot_size(&descriptor.return_type),
+                )
+            }
+            Instruction::invokestatic { descriptor, .. }
+            | Instruction::invokedynamic { descriptor, .. } => {
+                let descriptor = MethodDescriptor::parse(descriptor)?;
+                (
+                    descriptor.parameter_slots(),
+                    slot_size(&descriptor.return_type),
+                )
+            }
+            Instruction::getstatic { descriptor, .. } => (0, slot_size(descriptor)),
+            Instruction::putstatic { descriptor, .. } => (slot_size(descriptor), 0),
+            Instruction::getfield { descriptor, .. } => (1, slot_size(descriptor)),
+            Instruction::putfield { descriptor, .. } => (slot_size(descriptor) + 1, 0),
+            Instruction::new { .. } => (0, 1),
+            Instruction::checkcast { .. }
+            | Instruction::instanceof { .. }
+            | Instruction::arraylength
+            | Instruction::newarray { .. }
+            | Instruction::anewarray { .. } => (1, 1),
+            Instruction::multianewarray { dimensions, .. } => (*dimensions as u16, 1),
+            Instruction::iaload
+            | Instruction::faload
+            | Instruction::aaload
+            | Instruction::baload
+            | Instruction::caload
+            | Instruction::saload => (2, 1),
+            Instruction::laload | Instruction::daload => (2, 2),
+            Instruction::iastore
+            | Instruction::fastore
+            | Instruction::aastore
+            | Instruction::bastore
+            | Instruction::castore
+            | Instruction::sastore => (3, 0),
+            Instruction::lastore | Instruction::dastore => (4, 0),
+            Instruction::nop => (0, 0),
+            Instruction::pop => (1, 0),
+            Instruction::pop2 => (2, 0),
+            Instruction::dup => (1, 2),
+            Instruction::dup_x1 => (2, 3),
+            Instruction::dup_x2 => (3, 4),
+            Instruction::dup2 => (2, 4),
+            Instruction::dup2_x1 => (3, 5),
+            Instruction::dup2_x2 => (4, 6),
+            Instruction::swap => (2, 2),
+            Instruction::ireturn
+            | Instruction::freturn
+            | Instruction::areturn
+            | Instruction::athrow
+            | Instruction::monitorenter
+            | Instruction::monitorexit => (1, 0),
+            Instruction::lreturn | Instruction::dreturn => (2, 0),
+            Instruction::r#return => (0, 0),
+        })
+    }
+
+    /// Returns whether execution can continue to the next instruction after this one.
+    pub fn falls_through(&self) -> bool {
+        !matches!(
+            self,
+            Instruction::goto { .. }
+                | Instruction::goto_w { .. }
+                | Instruction::tableswitch { .. }
+                | Instruction::lookupswitch { .. }
+                | Instruction::ireturn
+                | Instruction::lreturn
+                | Instruction::freturn
+                | Instruction::dreturn
+                | Instruction::areturn
+                | Instruction::r#return
+                | Instruction::athrow
+        )
+    }
+
+    /// Returns whether this instruction returns from the method.
+    pub fn is_return(&self) -> bool {
+        matches!(
+            self,
+            Instruction::ireturn
+                | Instruction::lreturn
+                | Instruction::freturn
+                | Instruction::dreturn
+                | Instruction::areturn
+                | Instruction::r#return
+        )
+    }
+
+    /// Returns the conditional branch with the opposite condition to this one, jumping to `target`.
+    /// Returns [None] if this is not a conditional branch.
+    pub fn inverted(&self, target: Label) -> Option<Instruction> {
+        Some(match self {
+            Instruction::ifeq { .. } => Instruction::ifne { target },
+            Instruction::ifne { .. } => Instruction::ifeq { target },
+            Instruction::iflt { .. } => Instruction::ifge { target },
+            Instruction::ifge { .. } => Instruction::iflt { target },
+            Instruction::ifgt { .. } => Instruction::ifle { target },
+            Instruction::ifle { .. } => Instruction::ifgt { target },
+            Instruction::if_icmpeq { .. } => Instruction::if_icmpne { target },
+            Instruction::if_icmpne { .. } => Instruction::if_icmpeq { target },
+            Instruction::if_icmplt { .. } => Instruction::if_icmpge { target },
+            Instruction::if_icmpge { .. } => Instruction::if_icmplt { target },
+            Instruction::if_icmpgt { .. } => Instruction::if_icmple { target },
+            Instruction::if_icmple { .. } => Instruction::if_icmpgt { target },
+            Instruction::if_acmpeq { .. } => Instruction::if_acmpne { target },
+            Instruction::if_acmpne { .. } => Instruction::if_acmpeq { target },
+            Instruction::ifnull { .. } => Instruction::ifnonnull { target },
+            Instruction::ifnonnull { .. } => Instruction::ifnull { target },
+            _ => return None,
+        })
+    }
+
     /// Emits the instruction as a byte array.
-    pub fn emit(self, constant_pool: &mut ConstantPool) -> Result<Vec<u8>, Error> {
+    /// `offset` is the position of this instruction in the method's code, and `labels` maps each
+    /// placed [Label] to its position. These are used to resolve branch targets.
+    pub fn emit(
+        &self,
+        constant_pool: &mut ConstantPool,
+        offset: u32,
+        labels: &HashMap<Label, u32>,
+    ) -> Result<Vec<u8>, Error> {
         let mut bytes = Vec::new();
 
         match self {
-            Instruction::aload { index } => {
-                bytes.extend_from_slice(&[0x19]);
-                bytes.extend_from_slice(&index.to_be_bytes());
-            }
-            Instruction::iload { index } => {
-                bytes.extend_from_slice(&[0x15]);
-                bytes.extend_from_slice(&index.to_be_bytes());
+            Instruction::label { .. } => {}
+            Instruction::iload { .. }
+            | Instruction::lload { .. }
+            | Instruction::fload { .. }
+            | Instruction::dload { .. }
+            | Instruction::aload { .. }
+            | Instruction::istore { .. }
+            | Instruction::lstore { .. }
+            | Instruction::fstore { .. }
+            | Instruction::dstore { .. }
+            | Instruction::astore { .. } => {
+                let (opcode, short_opcode, _, index) = self.local_access().unwrap();
+                match index.slot()? {
+                    index @ 0..=3 => bytes.push(short_opcode + index as u8),
+                    index @ 0..=255 => bytes.extend_from_slice(&[opcode, index as u8]),
+                    index => {
+                        bytes.extend_from_slice(&[0xc4, opcode]);
+                        bytes.extend_from_slice(&index.to_be_bytes());
+                    }
+                }
             }
             Instruction::iconst { value } => match value {
                 -1 => bytes.extend_from_slice(&[0x02]),
@@ -61,27 +819,215 @@ impl Instruction {
                 3 => bytes.extend_from_slice(&[0x06]),
                 4 => bytes.extend_from_slice(&[0x07]),
                 5 => bytes.extend_from_slice(&[0x08]),
-                _ => Err(Error::InvalidIconst(value))?,
+                _ => Err(Error::InvalidIconst(*value))?,
+            },
+            Instruction::lconst_0 => bytes.push(0x09),
+            Instruction::lconst_1 => bytes.push(0x0a),
+            Instruction::fconst_0 => bytes.push(0x0b),
+            Instruction::fconst_1 => bytes.push(0x0c),
+            Instruction::fconst_2 => bytes.push(0x0d),
+            Instruction::dconst_0 => bytes.push(0x0e),
+            Instruction::dconst_1 => bytes.push(0x0f),
+            Instruction::bipush { value } => bytes.extend_from_slice(&[0x10, *value as u8]),
+            Instruction::sipush { value } => {
+                bytes.push(0x11);
+                bytes.extend_from_slice(&value.to_be_bytes());
+            }
+            Instruction::ldc { constant } => {
+                if constant.is_wide() {
+                    return Err(Error::InvalidConstant("ldc"));
+                }
+                let index = constant_pool.insert_constant(constant);
+                let index: u8 = index
+                    .try_into()
+                    .map_err(|_| Error::ConstantIndexOutOfRange(index))?;
+                bytes.extend_from_slice(&[0x12, index]);
+            }
+            Instruction::ldc_w { constant } => {
+                if constant.is_wide() {
+                    return Err(Error::InvalidConstant("ldc_w"));
+                }
+                bytes.push(0x13);
+                let index = constant_pool.insert_constant(constant);
+                bytes.extend_from_slice(&index.to_be_bytes());
+            }
+            Instruction::ldc2_w { constant } => {
+                if !constant.is_wide() {
+                    return Err(Error::InvalidConstant("ldc2_w"));
+                }
+                bytes.push(0x14);
+                let index = constant_pool.insert_constant(constant);
+                bytes.extend_from_slice(&index.to_be_bytes());
+            }
+            Instruction::iadd => bytes.push(0x60),
+            Instruction::ladd => bytes.push(0x61),
+            Instruction::fadd => bytes.push(0x62),
+            Instruction::dadd => bytes.push(0x63),
+            Instruction::isub => bytes.push(0x64),
+            Instruction::lsub => bytes.push(0x65),
+            Instruction::fsub => bytes.push(0x66),
+            Instruction::dsub => bytes.push(0x67),
+            Instruction::imul => bytes.push(0x68),
+            Instruction::lmul => bytes.push(0x69),
+            Instruction::fmul => bytes.push(0x6a),
+            Instruction::dmul => bytes.push(0x6b),
+            Instruction::idiv => bytes.push(0x6c),
+            Instruction::ldiv => bytes.push(0x6d),
+            Instruction::fdiv => bytes.push(0x6e),
+            Instruction::ddiv => bytes.push(0x6f),
+            Instruction::irem => bytes.push(0x70),
+            Instruction::lrem => bytes.push(0x71),
+            Instruction::frem => bytes.push(0x72),
+            Instruction::drem => bytes.push(0x73),
+            Instruction::ineg => bytes.push(0x74),
+            Instruction::lneg => bytes.push(0x75),
+            Instruction::fneg => bytes.push(0x76),
+            Instruction::dneg => bytes.push(0x77),
+            Instruction::ishl => bytes.push(0x78),
+            Instruction::lshl => bytes.push(0x79),
+            Instruction::ishr => bytes.push(0x7a),
+            Instruction::lshr => bytes.push(0x7b),
+            Instruction::iushr => bytes.push(0x7c),
+            Instruction::lushr => bytes.push(0x7d),
+            Instruction::iand => bytes.push(0x7e),
+            Instruction::land => bytes.push(0x7f),
+            Instruction::ior => bytes.push(0x80),
+            Instruction::lor => bytes.push(0x81),
+            Instruction::ixor => bytes.push(0x82),
+            Instruction::lxor => bytes.push(0x83),
+            Instruction::i2l => bytes.push(0x85),
+            Instruction::i2f => bytes.push(0x86),
+            Instruction::i2d => bytes.push(0x87),
+            Instruction::l2i => bytes.push(0x88),
+            Instruction::l2f => bytes.push(0x89),
+            Instruction::l2d => bytes.push(0x8a),
+            Instruction::f2i => bytes.push(0x8b),
+            Instruction::f2l => bytes.push(0x8c),
+            Instruction::f2d => bytes.push(0x8d),
+            Instruction::d2i => bytes.push(0x8e),
+            Instruction::d2l => bytes.push(0x8f),
+            Instruction::d2f => bytes.push(0x90),
+            Instruction::i2b => bytes.push(0x91),
+            Instruction::i2c => bytes.push(0x92),
+            Instruction::i2s => bytes.push(0x93),
+            Instruction::lcmp => bytes.push(0x94),
+            Instruction::fcmpl => bytes.push(0x95),
+            Instruction::fcmpg => bytes.push(0x96),
+            Instruction::dcmpl => bytes.push(0x97),
+            Instruction::dcmpg => bytes.push(0x98),
+            Instruction::iinc { index, value } => match (index.slot()?, *value) {
+                (index @ 0..=255, value @ -128..=127) => {
+                    bytes.extend_from_slice(&[0x84, index as u8, value as u8])
+                }
+                (index, value) => {
+                    bytes.extend_from_slice(&[0xc4, 0x84]);
+                    bytes.extend_from_slice(&index.to_be_bytes());
+                    bytes.extend_from_slice(&value.to_be_bytes());
+                }
             },
-            Instruction::iadd => {
-                bytes.extend_from_slice(&[0x60]);
+            Instruction::ifeq { target } => emit_branch(&mut bytes, 0x99, *target, offset, labels)?,
+            Instruction::ifne { target } => emit_branch(&mut bytes, 0x9a, *target, offset, labels)?,
+            Instruction::iflt { target } => emit_branch(&mut bytes, 0x9b, *target, offset, labels)?,
+            Instruction::ifge { target } => emit_branch(&mut bytes, 0x9c, *target, offset, labels)?,
+            Instruction::ifgt { target } => emit_branch(&mut bytes, 0x9d, *target, offset, labels)?,
+            Instruction::ifle { target } => emit_branch(&mut bytes, 0x9e, *target, offset, labels)?,
+            Instruction::if_icmpeq { target } => {
+                emit_branch(&mut bytes, 0x9f, *target, offset, labels)?
+            }
+            Instruction::if_icmpne { target } => {
+                emit_branch(&mut bytes, 0xa0, *target, offset, labels)?
+            }
+            Instruction::if_icmplt { target } => {
+                emit_branch(&mut bytes, 0xa1, *target, offset, labels)?
+            }
+            Instruction::if_icmpge { target } => {
+                emit_branch(&mut bytes, 0xa2, *target, offset, labels)?
+            }
+            Instruction::if_icmpgt { target } => {
+                emit_branch(&mut bytes, 0xa3, *target, offset, labels)?
+            }
+            Instruction::if_icmple { target } => {
+                emit_branch(&mut bytes, 0xa4, *target, offset, labels)?
+            }
+            Instruction::if_acmpeq { target } => {
+                emit_branch(&mut bytes, 0xa5, *target, offset, labels)?
+            }
+            Instruction::if_acmpne { target } => {
+                emit_branch(&mut bytes, 0xa6, *target, offset, labels)?
+            }
+            Instruction::goto { target } => emit_branch(&mut bytes, 0xa7, *target, offset, labels)?,
+            Instruction::jsr { target } => emit_branch(&mut bytes, 0xa8, *target, offset, labels)?,
+            Instruction::ifnull { target } => {
+                emit_branch(&mut bytes, 0xc6, *target, offset, labels)?
+            }
+            Instruction::ifnonnull { target } => {
+                emit_branch(&mut bytes, 0xc7, *target, offset, labels)?
+            }
+            Instruction::goto_w { target } => {
+                emit_wide_branch(&mut bytes, 0xc8, *target, offset, labels)?
+            }
+            Instruction::jsr_w { target } => {
+                emit_wide_branch(&mut bytes, 0xc9, *target, offset, labels)?
+            }
+            Instruction::tableswitch {
+                low,
+                targets,
+                default,
+            } => {
+                let high = (targets.len() as i64)
+                    .checked_sub(1)
+                    .and_then(|len| i32::try_from(*low as i64 + len).ok())
+                    .ok_or(Error::InvalidTableSwitch)?;
+                bytes.push(0xaa);
+                bytes.resize(1 + switch_padding(offset) as usize, 0);
+                emit_switch_offset(&mut bytes, *default, offset, labels)?;
+                bytes.extend_from_slice(&low.to_be_bytes());
+                bytes.extend_from_slice(&high.to_be_bytes());
+                for target in targets {
+                    emit_switch_offset(&mut bytes, *target, offset, labels)?;
+                }
+            }
+            Instruction::lookupswitch { cases, default } => {
+                let mut cases = cases.clone();
+                cases.sort_by_key(|(key, _)| *key);
+                if let Some(pair) = cases.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+                    return Err(Error::DuplicateSwitchCase(pair[0].0));
+                }
+                bytes.push(0xab);
+                bytes.resize(1 + switch_padding(offset) as usize, 0);
+                emit_switch_offset(&mut bytes, *default, offset, labels)?;
+                bytes.extend_from_slice(&(cases.len() as i32).to_be_bytes());
+                for (key, target) in cases {
+                    bytes.extend_from_slice(&key.to_be_bytes());
+                    emit_switch_offset(&mut bytes, target, offset, labels)?;
+                }
             }
             Instruction::invokespecial {
                 class,
                 name,
                 descriptor,
+                is_interface,
             } => {
                 bytes.extend_from_slice(&[0xb7]);
-                let index = constant_pool.insert_method(class, name, descriptor);
+                let index = if *is_interface {
+                    constant_pool.insert_interface_method(class, name, descriptor)
+                } else {
+                    constant_pool.insert_method(class, name, descriptor)
+                };
                 bytes.extend_from_slice(&index.to_be_bytes());
             }
             Instruction::invokestatic {
                 class,
                 name,
                 descriptor,
+                is_interface,
             } => {
                 bytes.extend_from_slice(&[0xb8]);
-                let index = constant_pool.insert_method(class, name, descriptor);
+                let index = if *is_interface {
+                    constant_pool.insert_interface_method(class, name, descriptor)
+                } else {
+                    constant_pool.insert_method(class, name, descriptor)
+                };
                 bytes.extend_from_slice(&index.to_be_bytes());
             }
             Instruction::invokevirtual {
@@ -93,6 +1039,29 @@ impl Instruction {
                 let index = constant_pool.insert_method(class, name, descriptor);
                 bytes.extend_from_slice(&index.to_be_bytes());
             }
+            Instruction::invokeinterface {
+                class,
+                name,
+                descriptor,
+            } => {
+                let count = MethodDescriptor::parse(descriptor)?.parameter_slots() + 1;
+                bytes.extend_from_slice(&[0xb9]);
+                let index = constant_pool.insert_interface_method(class, name, descriptor);
+                bytes.extend_from_slice(&index.to_be_bytes());
+                bytes.extend_from_slice(&[count as u8, 0]);
+            }
+            Instruction::invokedynamic {
+                name,
+                descriptor,
+                bootstrap_method,
+            } => {
+                bytes.extend_from_slice(&[0xba]);
+                let bootstrap_method = constant_pool.insert_bootstrap_method(bootstrap_method);
+                let name_and_type = constant_pool.insert_name_and_type(name, descriptor);
+                let index = constant_pool.insert_invoke_dynamic(bootstrap_method, name_and_type);
+                bytes.extend_from_slice(&index.to_be_bytes());
+                bytes.extend_from_slice(&[0, 0]);
+            }
             Instruction::getstatic {
                 class,
                 name,
@@ -102,74 +1071,1020 @@ impl Instruction {
                 let index = constant_pool.insert_field(class, name, descriptor);
                 bytes.extend_from_slice(&index.to_be_bytes());
             }
+            Instruction::putstatic {
+                class,
+                name,
+                descriptor,
+            } => {
+                bytes.extend_from_slice(&[0xb3]);
+                let index = constant_pool.insert_field(class, name, descriptor);
+                bytes.extend_from_slice(&index.to_be_bytes());
+            }
+            Instruction::getfield {
+                class,
+                name,
+                descriptor,
+            } => {
+                bytes.extend_from_slice(&[0xb4]);
+                let index = constant_pool.insert_field(class, name, descriptor);
+                bytes.extend_from_slice(&index.to_be_bytes());
+            }
+            Instruction::putfield {
+                class,
+                name,
+                descriptor,
+            } => {
+                bytes.extend_from_slice(&[0xb5]);
+                let index = constant_pool.insert_field(class, name, descriptor);
+                bytes.extend_from_slice(&index.to_be_bytes());
+            }
+            Instruction::new { class } => {
+                bytes.extend_from_slice(&[0xbb]);
+                let index = constant_pool.insert_class(class);
+                bytes.extend_from_slice(&index.to_be_bytes());
+            }
+            Instruction::checkcast { class } => {
+                bytes.extend_from_slice(&[0xc0]);
+                let index = constant_pool.insert_class(class);
+                bytes.extend_from_slice(&index.to_be_bytes());
+            }
+            Instruction::instanceof { class } => {
+                bytes.extend_from_slice(&[0xc1]);
+                let index = constant_pool.insert_class(class);
+                bytes.extend_from_slice(&index.to_be_bytes());
+            }
+            Instruction::arraylength => bytes.push(0xbe),
+            Instruction::newarray { ty } => bytes.extend_from_slice(&[0xbc, *ty as u8]),
+            Instruction::anewarray { class } => {
+                bytes.extend_from_slice(&[0xbd]);
+                let index = constant_pool.insert_class(class);
+                bytes.extend_from_slice(&index.to_be_bytes());
+            }
+            Instruction::multianewarray { class, dimensions } => {
+                let depth = class.bytes().take_while(|byte| *byte == b'[').count();
+                if *dimensions == 0 || *dimensions as usize > depth {
+                    return Err(Error::InvalidArrayDimensions(*dimensions));
+                }
+                bytes.extend_from_slice(&[0xc5]);
+                let index = constant_pool.insert_class(class);
+                bytes.extend_from_slice(&index.to_be_bytes());
+                bytes.push(*dimensions);
+            }
+            Instruction::iaload => bytes.push(0x2e),
+            Instruction::laload => bytes.push(0x2f),
+            Instruction::faload => bytes.push(0x30),
+            Instruction::daload => bytes.push(0x31),
+            Instruction::aaload => bytes.push(0x32),
+            Instruction::baload => bytes.push(0x33),
+            Instruction::caload => bytes.push(0x34),
+            Instruction::saload => bytes.push(0x35),
+            Instruction::iastore => bytes.push(0x4f),
+            Instruction::lastore => bytes.push(0x50),
+            Instruction::fastore => bytes.push(0x51),
+            Instruction::dastore => bytes.push(0x52),
+            Instruction::aastore => bytes.push(0x53),
+            Instruction::bastore => bytes.push(0x54),
+            Instruction::castore => bytes.push(0x55),
+            Instruction::sastore => bytes.push(0x56),
+            Instruction::nop => bytes.push(0x00),
+            Instruction::pop => bytes.push(0x57),
+            Instruction::pop2 => bytes.push(0x58),
+            Instruction::dup => bytes.push(0x59),
+            Instruction::dup_x1 => bytes.push(0x5a),
+            Instruction::dup_x2 => bytes.push(0x5b),
+            Instruction::dup2 => bytes.push(0x5c),
+            Instruction::dup2_x1 => bytes.push(0x5d),
+            Instruction::dup2_x2 => bytes.push(0x5e),
+            Instruction::swap => bytes.push(0x5f),
             Instruction::r#return => {
                 bytes.extend_from_slice(&[0xb1]);
             }
             Instruction::ireturn => {
                 bytes.extend_from_slice(&[0xac]);
             }
+            Instruction::lreturn => bytes.push(0xad),
+            Instruction::freturn => bytes.push(0xae),
+            Instruction::dreturn => bytes.push(0xaf),
+            Instruction::areturn => bytes.push(0xb0),
+            Instruction::athrow => {
+                bytes.extend_from_slice(&[0xbf]);
+            }
+            Instruction::monitorenter => bytes.push(0xc2),
+            Instruction::monitorexit => bytes.push(0xc3),
         }
 
         Ok(bytes)
     }
 }
 
-pub fn aload(index: u8) -> Instruction {
-    Instruction::aload { index }
+/// Emits a branch instruction with a signed 16-bit offset, relative to the start of the instruction.
+fn emit_branch(
+    bytes: &mut Vec<u8>,
+    opcode: u8,
+    target: Label,
+    offset: u32,
+    labels: &HashMap<Label, u32>,
+) -> Result<(), Error> {
+    let target = *labels.get(&target).ok_or(Error::UnplacedLabel)?;
+    let relative = target as i64 - offset as i64;
+    let relative: i16 = relative
+        .try_into()
+        .map_err(|_| Error::BranchOutOfRange(relative))?;
+    bytes.push(opcode);
+    bytes.extend_from_slice(&relative.to_be_bytes());
+    Ok(())
+}
+
+/// Emits a branch instruction with a signed 32-bit offset, relative to the start of the instruction.
+fn emit_wide_branch(
+    bytes: &mut Vec<u8>,
+    opcode: u8,
+    target: Label,
+    offset: u32,
+    labels: &HashMap<Label, u32>,
+) -> Result<(), Error> {
+    let target = *labels.get(&target).ok_or(Error::UnplacedLabel)?;
+    let relative = (target as i64 - offset as i64) as i32;
+    bytes.push(opcode);
+    bytes.extend_from_slice(&relative.to_be_bytes());
+    Ok(())
+}
+
+/// Returns the number of padding bytes after a switch opcode at `offset`, which align the operands
+/// to a multiple of 4 bytes from the start of the method.
+fn switch_padding(offset: u32) -> u32 {
+    (4 - (offset + 1) % 4) % 4
 }
 
+/// Emits the signed 32-bit offset of a switch target, relative to the start of the switch.
+fn emit_switch_offset(
+    bytes: &mut Vec<u8>,
+    target: Label,
+    offset: u32,
+    labels: &HashMap<Label, u32>,
+) -> Result<(), Error> {
+    let target = *labels.get(&target).ok_or(Error::UnplacedLabel)?;
+    let relative = (target as i64 - offset as i64) as i32;
+    bytes.extend_from_slice(&relative.to_be_bytes());
+    Ok(())
+}
+
+pub fn label(label: Label) -> Instruction {
+    Instruction::label { label }
+}
+
+pub fn aload(index: impl Into<LocalIndex>) -> Instruction {
+    Instruction::aload {
+        index: index.into(),
+    }
+}
+
+/// Pushes an `int` constant between -1 and 5. Use [push_int] for any other value.
 pub fn iconst(value: i32) -> Instruction {
-    if value < -1 || value > 5 {
+    if !(-1..=5).contains(&value) {
         panic!("Invalid iconst value: {}", value);
     }
 
     Instruction::iconst { value }
 }
 
-pub fn iload(index: u8) -> Instruction {
-    Instruction::iload { index }
+pub fn lconst_0() -> Instruction {
+    Instruction::lconst_0
 }
 
-pub fn iadd() -> Instruction {
-    Instruction::iadd
+pub fn lconst_1() -> Instruction {
+    Instruction::lconst_1
 }
 
-pub fn invokespecial(class: impl Into<String>, name: impl Into<String>, descriptor: impl Into<String>) -> Instruction {
-    Instruction::invokespecial {
-        class: class.into(),
-        name: name.into(),
-        descriptor: descriptor.into(),
+pub fn fconst_0() -> Instruction {
+    Instruction::fconst_0
+}
+
+pub fn fconst_1() -> Instruction {
+    Instruction::fconst_1
+}
+
+pub fn fconst_2() -> Instruction {
+    Instruction::fconst_2
+}
+
+pub fn dconst_0() -> Instruction {
+    Instruction::dconst_0
+}
+
+pub fn dconst_1() -> Instruction {
+    Instruction::dconst_1
+}
+
+pub fn bipush(value: i8) -> Instruction {
+    Instruction::bipush { value }
+}
+
+pub fn sipush(value: i16) -> Instruction {
+    Instruction::sipush { value }
+}
+
+pub fn ldc(constant: impl Into<Constant>) -> Instruction {
+    Instruction::ldc {
+        constant: constant.into(),
     }
 }
 
-pub fn invokevirtual(class: impl Into<String>, name: impl Into<String>, descriptor: impl Into<String>) -> Instruction {
-    Instruction::invokevirtual {
-        class: class.into(),
-        name: name.into(),
-        descriptor: descriptor.into(),
+pub fn ldc_w(constant: impl Into<Constant>) -> Instruction {
+    Instruction::ldc_w {
+        constant: constant.into(),
     }
 }
 
-pub fn invokestatic(class: impl Into<String>, name: impl Into<String>, descriptor: impl Into<String>) -> Instruction {
-    Instruction::invokestatic {
-        class: class.into(),
-        name: name.into(),
-        descriptor: descriptor.into(),
+pub fn ldc2_w(constant: impl Into<Constant>) -> Instruction {
+    Instruction::ldc2_w {
+        constant: constant.into(),
     }
 }
 
-pub fn getstatic(class: impl Into<String>, name: impl Into<String>, descriptor: impl Into<String>) -> Instruction {
-    Instruction::getstatic {
-        class: class.into(),
-        name: name.into(),
-        descriptor: descriptor.into(),
+/// Pushes an `int` constant using the smallest instruction that can encode it.
+pub fn push_int(value: i32) -> Instruction {
+    match value {
+        -1..=5 => Instruction::iconst { value },
+        _ => match (i8::try_from(value), i16::try_from(value)) {
+            (Ok(value), _) => bipush(value),
+            (_, Ok(value)) => sipush(value),
+            _ => ldc(value),
+        },
     }
 }
 
-pub fn ireturn() -> Instruction {
-    Instruction::ireturn
+/// Pushes a `long` constant using the smallest instruction that can encode it.
+pub fn push_long(value: i64) -> Instruction {
+    match value {
+        0 => lconst_0(),
+        1 => lconst_1(),
+        _ => ldc2_w(value),
+    }
 }
 
-pub fn r#return() -> Instruction {
-    Instruction::r#return
+/// Pushes a `float` constant using the smallest instruction that can encode it.
+/// `-0.0` is not equal to `0.0` here, so it is loaded from the constant pool.
+pub fn push_float(value: f32) -> Instruction {
+    match value.to_bits() {
+        bits if bits == 0f32.to_bits() => fconst_0(),
+        bits if bits == 1f32.to_bits() => fconst_1(),
+        bits if bits == 2f32.to_bits() => fconst_2(),
+        _ => ldc(value),
+    }
+}
+
+/// Pushes a `double` constant using the smallest instruction that can encode it.
+/// `-0.0` is not equal to `0.0` here, so it is loaded from the constant pool.
+pub fn push_double(value: f64) -> Instruction {
+    match value.to_bits() {
+        bits if bits == 0f64.to_bits() => dconst_0(),
+        bits if bits == 1f64.to_bits() => dconst_1(),
+        _ => ldc2_w(value),
+    }
+}
+
+pub fn iload(index: impl Into<LocalIndex>) -> Instruction {
+    Instruction::iload {
+        index: index.into(),
+    }
+}
+
+pub fn lload(index: impl Into<LocalIndex>) -> Instruction {
+    Instruction::lload {
+        index: index.into(),
+    }
+}
+
+pub fn fload(index: impl Into<LocalIndex>) -> Instruction {
+    Instruction::fload {
+        index: index.into(),
+    }
+}
+
+pub fn dload(index: impl Into<LocalIndex>) -> Instruction {
+    Instruction::dload {
+        index: index.into(),
+    }
+}
+
+pub fn istore(index: impl Into<LocalIndex>) -> Instruction {
+    Instruction::istore {
+        index: index.into(),
+    }
+}
+
+pub fn lstore(index: impl Into<LocalIndex>) -> Instruction {
+    Instruction::lstore {
+        index: index.into(),
+    }
+}
+
+pub fn fstore(index: impl Into<LocalIndex>) -> Instruction {
+    Instruction::fstore {
+        index: index.into(),
+    }
+}
+
+pub fn dstore(index: impl Into<LocalIndex>) -> Instruction {
+    Instruction::dstore {
+        index: index.into(),
+    }
+}
+
+pub fn astore(index: impl Into<LocalIndex>) -> Instruction {
+    Instruction::astore {
+        index: index.into(),
+    }
+}
+
+pub fn iadd() -> Instruction {
+    Instruction::iadd
+}
+
+pub fn ladd() -> Instruction {
+    Instruction::ladd
+}
+
+pub fn fadd() -> Instruction {
+    Instruction::fadd
+}
+
+pub fn dadd() -> Instruction {
+    Instruction::dadd
+}
+
+pub fn isub() -> Instruction {
+    Instruction::isub
+}
+
+pub fn lsub() -> Instruction {
+    Instruction::lsub
+}
+
+pub fn fsub() -> Instruction {
+    Instruction::fsub
+}
+
+pub fn dsub() -> Instruction {
+    Instruction::dsub
+}
+
+pub fn imul() -> Instruction {
+    Instruction::imul
+}
+
+pub fn lmul() -> Instruction {
+    Instruction::lmul
+}
+
+pub fn fmul() -> Instruction {
+    Instruction::fmul
+}
+
+pub fn dmul() -> Instruction {
+    Instruction::dmul
+}
+
+pub fn idiv() -> Instruction {
+    Instruction::idiv
+}
+
+pub fn ldiv() -> Instruction {
+    Instruction::ldiv
+}
+
+pub fn fdiv() -> Instruction {
+    Instruction::fdiv
+}
+
+pub fn ddiv() -> Instruction {
+    Instruction::ddiv
+}
+
+pub fn irem() -> Instruction {
+    Instruction::irem
+}
+
+pub fn lrem() -> Instruction {
+    Instruction::lrem
+}
+
+pub fn frem() -> Instruction {
+    Instruction::frem
+}
+
+pub fn drem() -> Instruction {
+    Instruction::drem
+}
+
+pub fn ineg() -> Instruction {
+    Instruction::ineg
+}
+
+pub fn lneg() -> Instruction {
+    Instruction::lneg
+}
+
+pub fn fneg() -> Instruction {
+    Instruction::fneg
+}
+
+pub fn dneg() -> Instruction {
+    Instruction::dneg
+}
+
+pub fn ishl() -> Instruction {
+    Instruction::ishl
+}
+
+pub fn lshl() -> Instruction {
+    Instruction::lshl
+}
+
+pub fn ishr() -> Instruction {
+    Instruction::ishr
+}
+
+pub fn lshr() -> Instruction {
+    Instruction::lshr
+}
+
+pub fn iushr() -> Instruction {
+    Instruction::iushr
+}
+
+pub fn lushr() -> Instruction {
+    Instruction::lushr
+}
+
+pub fn iand() -> Instruction {
+    Instruction::iand
+}
+
+pub fn land() -> Instruction {
+    Instruction::land
+}
+
+pub fn ior() -> Instruction {
+    Instruction::ior
+}
+
+pub fn lor() -> Instruction {
+    Instruction::lor
+}
+
+pub fn ixor() -> Instruction {
+    Instruction::ixor
+}
+
+pub fn lxor() -> Instruction {
+    Instruction::lxor
+}
+
+pub fn iinc(index: impl Into<LocalIndex>, value: i16) -> Instruction {
+    Instruction::iinc {
+        index: index.into(),
+        value,
+    }
+}
+
+pub fn i2l() -> Instruction {
+    Instruction::i2l
+}
+
+pub fn i2f() -> Instruction {
+    Instruction::i2f
+}
+
+pub fn i2d() -> Instruction {
+    Instruction::i2d
+}
+
+pub fn l2i() -> Instruction {
+    Instruction::l2i
+}
+
+pub fn l2f() -> Instruction {
+    Instruction::l2f
+}
+
+pub fn l2d() -> Instruction {
+    Instruction::l2d
+}
+
+pub fn f2i() -> Instruction {
+    Instruction::f2i
+}
+
+pub fn f2l() -> Instruction {
+    Instruction::f2l
+}
+
+pub fn f2d() -> Instruction {
+    Instruction::f2d
+}
+
+pub fn d2i() -> Instruction {
+    Instruction::d2i
+}
+
+pub fn d2l() -> Instruction {
+    Instruction::d2l
+}
+
+pub fn d2f() -> Instruction {
+    Instruction::d2f
+}
+
+pub fn i2b() -> Instruction {
+    Instruction::i2b
+}
+
+pub fn i2c() -> Instruction {
+    Instruction::i2c
+}
+
+pub fn i2s() -> Instruction {
+    Instruction::i2s
+}
+
+pub fn lcmp() -> Instruction {
+    Instruction::lcmp
+}
+
+pub fn fcmpl() -> Instruction {
+    Instruction::fcmpl
+}
+
+pub fn fcmpg() -> Instruction {
+    Instruction::fcmpg
+}
+
+pub fn dcmpl() -> Instruction {
+    Instruction::dcmpl
+}
+
+pub fn dcmpg() -> Instruction {
+    Instruction::dcmpg
+}
+
+pub fn ifeq(target: Label) -> Instruction {
+    Instruction::ifeq { target }
+}
+
+pub fn ifne(target: Label) -> Instruction {
+    Instruction::ifne { target }
+}
+
+pub fn iflt(target: Label) -> Instruction {
+    Instruction::iflt { target }
+}
+
+pub fn ifge(target: Label) -> Instruction {
+    Instruction::ifge { target }
+}
+
+pub fn ifgt(target: Label) -> Instruction {
+    Instruction::ifgt { target }
+}
+
+pub fn ifle(target: Label) -> Instruction {
+    Instruction::ifle { target }
+}
+
+pub fn if_icmpeq(target: Label) -> Instruction {
+    Instruction::if_icmpeq { target }
+}
+
+pub fn if_icmpne(target: Label) -> Instruction {
+    Instruction::if_icmpne { target }
+}
+
+pub fn if_icmplt(target: Label) -> Instruction {
+    Instruction::if_icmplt { target }
+}
+
+pub fn if_icmpge(target: Label) -> Instruction {
+    Instruction::if_icmpge { target }
+}
+
+pub fn if_icmpgt(target: Label) -> Instruction {
+    Instruction::if_icmpgt { target }
+}
+
+pub fn if_icmple(target: Label) -> Instruction {
+    Instruction::if_icmple { target }
+}
+
+pub fn if_acmpeq(target: Label) -> Instruction {
+    Instruction::if_acmpeq { target }
+}
+
+pub fn if_acmpne(target: Label) -> Instruction {
+    Instruction::if_acmpne { target }
+}
+
+pub fn goto(target: Label) -> Instruction {
+    Instruction::goto { target }
+}
+
+/// Jumps to a subroutine. This is only valid in class files older than version 51 (Java 7).
+pub fn jsr(target: Label) -> Instruction {
+    Instruction::jsr { target }
+}
+
+pub fn ifnull(target: Label) -> Instruction {
+    Instruction::ifnull { target }
+}
+
+pub fn ifnonnull(target: Label) -> Instruction {
+    Instruction::ifnonnull { target }
+}
+
+pub fn goto_w(target: Label) -> Instruction {
+    Instruction::goto_w { target }
+}
+
+/// Jumps to a subroutine. This is only valid in class files older than version 51 (Java 7).
+pub fn jsr_w(target: Label) -> Instruction {
+    Instruction::jsr_w { target }
+}
+
+pub fn tableswitch(low: i32, targets: impl IntoIterator<Item = Label>, default: Label) -> Instruction {
+    Instruction::tableswitch {
+        low,
+        targets: targets.into_iter().collect(),
+        default,
+    }
+}
+
+pub fn lookupswitch(cases: impl IntoIterator<Item = (i32, Label)>, default: Label) -> Instruction {
+    Instruction::lookupswitch {
+        cases: cases.into_iter().collect(),
+        default,
+    }
+}
+
+/// Switches on an `int`, choosing between `tableswitch` and `lookupswitch` with the same cost
+/// estimate as javac. Values missing from a `tableswitch` range jump to `default`.
+pub fn switch(cases: impl IntoIterator<Item = (i32, Label)>, default: Label) -> Instruction {
+    let mut cases: Vec<_> = cases.into_iter().collect();
+    cases.sort_by_key(|(key, _)| *key);
+    let distinct = cases.windows(2).all(|pair| pair[0].0 != pair[1].0);
+
+    let (Some((low, _)), Some((high, _))) = (cases.first(), cases.last()) else {
+        return lookupswitch(cases, default);
+    };
+    let (low, high, count) = (*low as i64, *high as i64, cases.len() as i64);
+    let table_cost = 4 + (high - low + 1) + 3 * 3;
+    let lookup_cost = 3 + 2 * count + 3 * count;
+    if !distinct || table_cost > lookup_cost {
+        return lookupswitch(cases, default);
+    }
+
+    let mut targets = vec![default; (high - low + 1) as usize];
+    for (key, target) in cases {
+        targets[(key as i64 - low) as usize] = target;
+    }
+    tableswitch(low as i32, targets, default)
+}
+
+pub fn invokespecial(class: impl Into<String>, name: impl Into<String>, descriptor: impl Into<String>) -> Instruction {
+    Instruction::invokespecial {
+        class: class.into(),
+        name: name.into(),
+        descriptor: descriptor.into(),
+        is_interface: false,
+    }
+}
+
+/// Like [invokespecial], but for a method declared in an interface.
+pub fn invokespecial_interface(
+    class: impl Into<String>,
+    name: impl Into<String>,
+    descriptor: impl Into<String>,
+) -> Instruction {
+    Instruction::invokespecial {
+        class: class.into(),
+        name: name.into(),
+        descriptor: descriptor.into(),
+        is_interface: true,
+    }
+}
+
+pub fn invokevirtual(class: impl Into<String>, name: impl Into<String>, descriptor: impl Into<String>) -> Instruction {
+    Instruction::invokevirtual {
+        class: class.into(),
+        name: name.into(),
+        descriptor: descriptor.into(),
+    }
+}
+
+pub fn invokestatic(class: impl Into<String>, name: impl Into<String>, descriptor: impl Into<String>) -> Instruction {
+    Instruction::invokestatic {
+        class: class.into(),
+        name: name.into(),
+        descriptor: descriptor.into(),
+        is_interface: false,
+    }
+}
+
+/// Like [invokestatic], but for a method declared in an interface.
+pub fn invokestatic_interface(
+    class: impl Into<String>,
+    name: impl Into<String>,
+    descriptor: impl Into<String>,
+) -> Instruction {
+    Instruction::invokestatic {
+        class: class.into(),
+        name: name.into(),
+        descriptor: descriptor.into(),
+        is_interface: true,
+    }
+}
+
+/// Calls an interface method. The `count` operand is computed from the descriptor.
+pub fn invokeinterface(
+    class: impl Into<String>,
+    name: impl Into<String>,
+    descriptor: impl Into<String>,
+) -> Instruction {
+    Instruction::invokeinterface {
+        class: class.into(),
+        name: name.into(),
+        descriptor: descriptor.into(),
+    }
+}
+
+pub fn invokedynamic(
+    name: impl Into<String>,
+    descriptor: impl Into<String>,
+    bootstrap_method: BootstrapMethod,
+) -> Instruction {
+    Instruction::invokedynamic {
+        name: name.into(),
+        descriptor: descriptor.into(),
+        bootstrap_method,
+    }
+}
+
+pub fn getstatic(class: impl Into<String>, name: impl Into<String>, descriptor: impl Into<String>) -> Instruction {
+    Instruction::getstatic {
+        class: class.into(),
+        name: name.into(),
+        descriptor: descriptor.into(),
+    }
+}
+
+pub fn putstatic(class: impl Into<String>, name: impl Into<String>, descriptor: impl Into<String>) -> Instruction {
+    Instruction::putstatic {
+        class: class.into(),
+        name: name.into(),
+        descriptor: descriptor.into(),
+    }
+}
+
+pub fn getfield(class: impl Into<String>, name: impl Into<String>, descriptor: impl Into<String>) -> Instruction {
+    Instruction::getfield {
+        class: class.into(),
+        name: name.into(),
+        descriptor: descriptor.into(),
+    }
+}
+
+pub fn putfield(class: impl Into<String>, name: impl Into<String>, descriptor: impl Into<String>) -> Instruction {
+    Instruction::putfield {
+        class: class.into(),
+        name: name.into(),
+        descriptor: descriptor.into(),
+    }
+}
+
+pub fn new(class: impl Into<String>) -> Instruction {
+    Instruction::new {
+        class: class.into(),
+    }
+}
+
+pub fn checkcast(class: impl Into<String>) -> Instruction {
+    Instruction::checkcast {
+        class: class.into(),
+    }
+}
+
+pub fn instanceof(class: impl Into<String>) -> Instruction {
+    Instruction::instanceof {
+        class: class.into(),
+    }
+}
+
+pub fn arraylength() -> Instruction {
+    Instruction::arraylength
+}
+
+pub fn newarray(ty: ArrayType) -> Instruction {
+    Instruction::newarray { ty }
+}
+
+pub fn anewarray(class: impl Into<String>) -> Instruction {
+    Instruction::anewarray {
+        class: class.into(),
+    }
+}
+
+pub fn multianewarray(class: impl Into<String>, dimensions: u8) -> Instruction {
+    Instruction::multianewarray {
+        class: class.into(),
+        dimensions,
+    }
+}
+
+pub fn iaload() -> Instruction {
+    Instruction::iaload
+}
+
+pub fn laload() -> Instruction {
+    Instruction::laload
+}
+
+pub fn faload() -> Instruction {
+    Instruction::faload
+}
+
+pub fn daload() -> Instruction {
+    Instruction::daload
+}
+
+pub fn aaload() -> Instruction {
+    Instruction::aaload
+}
+
+pub fn baload() -> Instruction {
+    Instruction::baload
+}
+
+pub fn caload() -> Instruction {
+    Instruction::caload
+}
+
+pub fn saload() -> Instruction {
+    Instruction::saload
+}
+
+pub fn iastore() -> Instruction {
+    Instruction::iastore
+}
+
+pub fn lastore() -> Instruction {
+    Instruction::lastore
+}
+
+pub fn fastore() -> Instruction {
+    Instruction::fastore
+}
+
+pub fn dastore() -> Instruction {
+    Instruction::dastore
+}
+
+pub fn aastore() -> Instruction {
+    Instruction::aastore
+}
+
+pub fn bastore() -> Instruction {
+    Instruction::bastore
+}
+
+pub fn castore() -> Instruction {
+    Instruction::castore
+}
+
+pub fn sastore() -> Instruction {
+    Instruction::sastore
+}
+
+pub fn nop() -> Instruction {
+    Instruction::nop
+}
+
+pub fn pop() -> Instruction {
+    Instruction::pop
+}
+
+pub fn pop2() -> Instruction {
+    Instruction::pop2
+}
+
+pub fn dup() -> Instruction {
+    Instruction::dup
+}
+
+pub fn dup_x1() -> Instruction {
+    Instruction::dup_x1
+}
+
+pub fn dup_x2() -> Instruction {
+    Instruction::dup_x2
+}
+
+pub fn dup2() -> Instruction {
+    Instruction::dup2
+}
+
+pub fn dup2_x1() -> Instruction {
+    Instruction::dup2_x1
+}
+
+pub fn dup2_x2() -> Instruction {
+    Instruction::dup2_x2
+}
+
+pub fn swap() -> Instruction {
+    Instruction::swap
+}
+
+/// Pops a value of the type given by the field descriptor `ty`, using `pop2` for `long` and
+/// `double`.
+pub fn pop_value(ty: &str) -> Instruction {
+    match slot_size(ty) {
+        2 => pop2(),
+        _ => pop(),
+    }
+}
+
+/// Duplicates a value of the type given by the field descriptor `ty`, using `dup2` for `long` and
+/// `double`.
+pub fn dup_value(ty: &str) -> Instruction {
+    match slot_size(ty) {
+        2 => dup2(),
+        _ => dup(),
+    }
+}
+
+/// Duplicates a value of the type given by the field descriptor `ty` and inserts it beneath the
+/// single-slot value below it, such as an object whose field is being set.
+pub fn dup_value_x1(ty: &str) -> Instruction {
+    match slot_size(ty) {
+        2 => dup2_x1(),
+        _ => dup_x1(),
+    }
+}
+
+/// Duplicates a value of the type given by the field descriptor `ty` and inserts it beneath the
+/// two slots below it, such as an array and index which are being stored to.
+pub fn dup_value_x2(ty: &str) -> Instruction {
+    match slot_size(ty) {
+        2 => dup2_x2(),
+        _ => dup_x2(),
+    }
+}
+
+pub fn ireturn() -> Instruction {
+    Instruction::ireturn
+}
+
+pub fn lreturn() -> Instruction {
+    Instruction::lreturn
+}
+
+pub fn freturn() -> Instruction {
+    Instruction::freturn
+}
+
+pub fn dreturn() -> Instruction {
+    Instruction::dreturn
+}
+
+pub fn areturn() -> Instruction {
+    Instruction::areturn
+}
+
+/// Returns a value of the type given by the descriptor `ty` from the method, or returns nothing if
+/// `ty` is `V`.
+pub fn return_value(ty: &str) -> Instruction {
+    match ty.as_bytes().first() {
+        Some(b'B' | b'C' | b'I' | b'S' | b'Z') => ireturn(),
+        Some(b'J') => lreturn(),
+        Some(b'F') => freturn(),
+        Some(b'D') => dreturn(),
+        Some(b'V') => r#return(),
+        _ => areturn(),
+    }
+}
+
+pub fn r#return() -> Instruction {
+    Instruction::r#return
+}
+
+pub fn athrow() -> Instruction {
+    Instruction::athrow
+}
+
+pub fn monitorenter() -> Instruction {
+    Instruction::monitorenter
+}
+
+pub fn monitorexit() -> Instruction {
+    Instruction::monitorexit
 }
@@ -0,0 +1,5 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// A position in the code of a method, used as the target of branch instructions.
+/// Labels are created with [CodeBuilder::new_label](crate::attribute::code::CodeBuilder::new_label)
+/// and placed into the instruction stream with [label](crate::instruction::label).
+pub struct Label(pub(crate) u32);
@@ -1,13 +1,24 @@
 use access_flag::AccessFlag;
+use attribute::Attribute;
+use class_hierarchy::{ClassHierarchy, DefaultClassHierarchy, WithClass};
 use constant_pool::ConstantPool;
+use field::FieldBuilder;
+use instruction::Instruction;
+use local::LocalType;
 use method::MethodBuilder;
 use thiserror::Error;
 
 pub mod access_flag;
+pub mod annotation;
 pub mod attribute;
+pub mod class_hierarchy;
 pub mod constant_pool;
 pub mod consts;
+pub mod descriptor;
+pub mod field;
 pub mod instruction;
+pub mod label;
+pub mod local;
 pub mod method;
 
 #[cfg(test)]
@@ -18,6 +29,54 @@ mod functional_tests;
 pub enum Error {
     #[error("Invalid iconst value (must be -1 to 5 inc.): {0}")]
     InvalidIconst(i32),
+    #[error("Constant cannot be loaded with {0}")]
+    InvalidConstant(&'static str),
+    #[error("Constant pool index out of range for ldc (must fit in 8 bits): {0}")]
+    ConstantIndexOutOfRange(u16),
+    #[error("A label was used as a branch target but never placed")]
+    UnplacedLabel,
+    #[error("A label was placed more than once")]
+    DuplicateLabel,
+    #[error("Branch offset out of range (must fit in 16 bits): {0}")]
+    BranchOutOfRange(i64),
+    #[error("Code too large (must fit in 65535 bytes): {0}")]
+    CodeTooLarge(usize),
+    #[error("A tableswitch must have at least one target, and its high key must fit in 32 bits")]
+    InvalidTableSwitch,
+    #[error("Duplicate lookupswitch case: {0}")]
+    DuplicateSwitchCase(i32),
+    #[error("String too long for the constant pool (must fit in 65535 bytes): {0}")]
+    StringTooLong(usize),
+    #[error("Invalid multianewarray dimensions (must be 1 to those of the array type): {0}")]
+    InvalidArrayDimensions(u8),
+    #[error("Invalid descriptor: {0}")]
+    InvalidDescriptor(String),
+    #[error("Operand stack underflow at offset {0}")]
+    StackUnderflow(u32),
+    #[error("Inconsistent operand stack height at offset {0}")]
+    StackHeightMismatch(u32),
+    #[error("Incompatible operand stack types at offset {0}")]
+    StackTypeMismatch(u32),
+    #[error("Execution can fall off the end of the code")]
+    CodeFallsOffEnd,
+    #[error("Unsupported instruction: {0}")]
+    UnsupportedInstruction(&'static str),
+    #[error("A local was used before it was given a slot")]
+    UnresolvedLocal,
+    #[error("Local variable index out of range")]
+    LocalIndexOutOfRange,
+    #[error("{0:?} cannot access a local of type {1:?}")]
+    LocalTypeMismatch(Box<Instruction>, LocalType),
+    #[error("A constant value must be on a static field and match its type {0}")]
+    InvalidConstantValue(String),
+    #[error("{0:?} does not match the method's return type {1}")]
+    ReturnTypeMismatch(Box<Instruction>, String),
+    #[error("Class not known to the class hierarchy: {0}")]
+    UnknownClass(String),
+    #[error("Invalid class file")]
+    InvalidClassFile,
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
 }
 
 /// A builder for an individual class file.
@@ -27,7 +86,10 @@ pub struct ClassFileBuilder {
     constant_pool: ConstantPool,
     class_name: Option<String>,
     super_class: Option<String>,
+    interfaces: Vec<String>,
+    fields: Vec<FieldBuilder>,
     methods: Vec<MethodBuilder>,
+    class_hierarchy: Box<dyn ClassHierarchy>,
 }
 
 impl ClassFileBuilder {
@@ -38,7 +100,10 @@ impl ClassFileBuilder {
             constant_pool: ConstantPool::new(),
             class_name: None,
             super_class: None,
+            interfaces: Vec::new(),
+            fields: Vec::new(),
             methods: Vec::new(),
+            class_hierarchy: Box::new(DefaultClassHierarchy::new()),
         }
     }
 
@@ -64,12 +129,32 @@ impl ClassFileBuilder {
         self
     }
 
+    /// Adds an interface implemented by the class.
+    /// This is in the internal format, `java/lang/Runnable`.
+    pub fn interface(mut self, interface: impl Into<String>) -> Self {
+        self.interfaces.push(interface.into());
+        self
+    }
+
+    /// Adds a field to the class.
+    pub fn field(mut self, field: FieldBuilder) -> Self {
+        self.fields.push(field);
+        self
+    }
+
     /// Adds a method to the class.
     pub fn method(mut self, method: MethodBuilder) -> Self {
         self.methods.push(method);
         self
     }
 
+    /// Sets the [ClassHierarchy] used to merge types when computing `StackMapTable` frames.
+    /// By default, this is a [DefaultClassHierarchy], which only knows the core `java.lang` classes.
+    pub fn class_hierarchy(mut self, class_hierarchy: impl ClassHierarchy + 'static) -> Self {
+        self.class_hierarchy = Box::new(class_hierarchy);
+        self
+    }
+
     /// Emits the class file to a vector of bytes.
     pub fn emit(mut self) -> Result<Vec<u8>, Error> {
         let mut bytes = Vec::new();
@@ -77,21 +162,58 @@ impl ClassFileBuilder {
         bytes.extend_from_slice(&consts::MINOR_VERSION.to_be_bytes());
         bytes.extend_from_slice(&consts::MAJOR_VERSION.to_be_bytes());
 
+        let class_name = self.class_name.unwrap();
+        let super_class_name = self
+            .super_class
+            .unwrap_or(consts::OBJECT_CLASS_NAME.to_string());
+        let hierarchy = WithClass {
+            inner: self.class_hierarchy.as_ref(),
+            class: &class_name,
+            super_class: &super_class_name,
+        };
+
+        let field_count = self.fields.len();
+        let field_bytes: Vec<u8> = self
+            .fields
+            .into_iter()
+            .map(|f| f.emit(&mut self.constant_pool))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .flatten()
+            .collect();
+
         let method_count = self.methods.len();
         let method_bytes: Vec<u8> = self
             .methods
             .into_iter()
-            .map(|m| m.emit(&mut self.constant_pool))
+            .map(|m| m.emit(&mut self.constant_pool, &class_name, &hierarchy))
             .collect::<Result<Vec<_>, _>>()?
             .into_iter()
             .flatten()
             .collect();
 
-        let self_class = self.constant_pool.insert_class(self.class_name.unwrap());
-        let super_class = self.constant_pool.insert_class(
-            self.super_class
-                .unwrap_or(consts::OBJECT_CLASS_NAME.to_string()),
-        );
+        let self_class = self.constant_pool.insert_class(class_name);
+        let super_class = self.constant_pool.insert_class(super_class_name);
+        let interfaces: Vec<u16> = self
+            .interfaces
+            .into_iter()
+            .map(|interface| self.constant_pool.insert_class(interface))
+            .collect();
+
+        let mut attributes = Vec::new();
+        if !self.constant_pool.bootstrap_methods().is_empty() {
+            attributes.push(Attribute::BootstrapMethods {
+                methods: self.constant_pool.bootstrap_methods().to_vec(),
+            });
+        }
+        let attribute_count = attributes.len();
+        let attribute_bytes: Vec<u8> = attributes
+            .into_iter()
+            .map(|attribute| attribute.emit(&mut self.constant_pool))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .flatten()
+            .collect();
 
         bytes.extend::<Vec<u8>>(self.constant_pool.emit()?);
 
@@ -106,13 +228,20 @@ impl ClassFileBuilder {
 
         bytes.extend(self_class.to_be_bytes());
         bytes.extend(super_class.to_be_bytes());
-        bytes.extend_from_slice(&[0x00, 0x00]);
-        bytes.extend_from_slice(&[0x00, 0x00]);
+
+        bytes.extend_from_slice(&(interfaces.len() as u16).to_be_bytes());
+        for interface in interfaces {
+            bytes.extend(interface.to_be_bytes());
+        }
+
+        bytes.extend_from_slice(&(field_count as u16).to_be_bytes());
+        bytes.extend(field_bytes);
 
         bytes.extend_from_slice(&(method_count as u16).to_be_bytes());
         bytes.extend(method_bytes);
 
-        bytes.extend_from_slice(&[0x00, 0x00]);
+        bytes.extend_from_slice(&(attribute_count as u16).to_be_bytes());
+        bytes.extend(attribute_bytes);
         Ok(bytes)
     }
 }
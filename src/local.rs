@@ -0,0 +1,148 @@
+use crate::{descriptor::validate_field_type, Error};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// A local variable allocated by [CodeBuilder::new_local](crate::attribute::code::CodeBuilder::new_local).
+/// Locals are numbered after the method's parameters, and are given their final slot when the code
+/// is built.
+pub struct Local {
+    slot: u16,
+    ty: LocalType,
+}
+
+impl Local {
+    /// Returns the number of slots taken by the local, which is 2 for `long` and `double`, and 1
+    /// otherwise.
+    pub fn size(&self) -> u16 {
+        match self.ty {
+            LocalType::Long | LocalType::Double => 2,
+            _ => 1,
+        }
+    }
+
+    /// Returns the type of the local.
+    pub fn local_type(&self) -> LocalType {
+        self.ty
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The type of a [Local], which decides the load and store instructions that can access it.
+/// `boolean`, `byte`, `char` and `short` are all stored as [LocalType::Int].
+pub enum LocalType {
+    Int,
+    Long,
+    Float,
+    Double,
+    Reference,
+}
+
+impl LocalType {
+    /// Returns the type of a local holding a value with the given field descriptor, or
+    /// [Error::InvalidDescriptor] if it is not a valid field type.
+    pub fn from_descriptor(descriptor: &str) -> Result<Self, Error> {
+        validate_field_type(descriptor)?;
+        Ok(match descriptor.as_bytes()[0] {
+            b'B' | b'C' | b'I' | b'S' | b'Z' => LocalType::Int,
+            b'J' => LocalType::Long,
+            b'F' => LocalType::Float,
+            b'D' => LocalType::Double,
+            _ => LocalType::Reference,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The local variable accessed by an instruction: either a raw slot index, or a [Local].
+pub enum LocalIndex {
+    Slot(u16),
+    Local(Local),
+}
+
+impl LocalIndex {
+    /// Returns the slot index, or an error if this is a [Local] that has not been resolved yet.
+    pub fn slot(&self) -> Result<u16, Error> {
+        match self {
+            LocalIndex::Slot(slot) => Ok(*slot),
+            LocalIndex::Local(_) => Err(Error::UnresolvedLocal),
+        }
+    }
+
+    /// Replaces a [Local] with its slot, given the first slot available to locals.
+    pub(crate) fn resolve(&mut self, base: u16) -> Result<(), Error> {
+        if let LocalIndex::Local(local) = self {
+            let slot = base
+                .checked_add(local.slot)
+                .ok_or(Error::LocalIndexOutOfRange)?;
+            *self = LocalIndex::Slot(slot);
+        }
+        Ok(())
+    }
+}
+
+impl From<u8> for LocalIndex {
+    fn from(slot: u8) -> Self {
+        LocalIndex::Slot(slot as u16)
+    }
+}
+
+impl From<u16> for LocalIndex {
+    fn from(slot: u16) -> Self {
+        LocalIndex::Slot(slot)
+    }
+}
+
+/// Allows untyped integer literals, such as `aload(0)`, to be used as slot indices.
+/// Panics if the slot is negative or does not fit in 16 bits.
+impl From<i32> for LocalIndex {
+    fn from(slot: i32) -> Self {
+        match u16::try_from(slot) {
+            Ok(slot) => LocalIndex::Slot(slot),
+            Err(_) => panic!("Invalid local variable slot: {}", slot),
+        }
+    }
+}
+
+impl From<Local> for LocalIndex {
+    fn from(local: Local) -> Self {
+        LocalIndex::Local(local)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+/// Allocates [Local]s in nested scopes. When a scope ends, the slots of the locals allocated inside
+/// it are reused by later locals.
+pub struct Locals {
+    next: u16,
+    scopes: Vec<u16>,
+}
+
+impl Locals {
+    /// Creates a new [Locals] allocator with no locals.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allocates a new local of the given type, such as `I` or `Ljava/lang/String;`.
+    /// Panics if `descriptor` is not a valid field type.
+    pub fn allocate(&mut self, descriptor: &str) -> Local {
+        let local = Local {
+            slot: self.next,
+            ty: LocalType::from_descriptor(descriptor)
+                .unwrap_or_else(|_| panic!("Invalid local variable type: {}", descriptor)),
+        };
+        self.next += local.size();
+        local
+    }
+
+    /// Starts a new scope.
+    pub fn begin_scope(&mut self) {
+        self.scopes.push(self.next);
+    }
+
+    /// Ends the innermost scope, freeing the locals allocated since it began.
+    pub fn end_scope(&mut self) {
+        if let Some(next) = self.scopes.pop() {
+            self.next = next;
+        }
+    }
+}
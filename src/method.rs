@@ -1,5 +1,10 @@
 use crate::{
-    access_flag::AccessFlag, attribute::code::CodeBuilder, constant_pool::ConstantPool, Error,
+    access_flag::AccessFlag,
+    attribute::code::{CodeBuilder, MethodContext},
+    class_hierarchy::ClassHierarchy,
+    constant_pool::ConstantPool,
+    instruction,
+    Error,
 };
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -61,9 +66,19 @@ impl MethodBuilder {
     }
 
     /// Emits the method to a vector of bytes.
-    pub fn emit(self, constant_pool: &mut ConstantPool) -> Result<Vec<u8>, Error> {
+    /// Returns [Error::ReturnTypeMismatch] if any return instruction in the code does not match the
+    /// method's return type.
+    /// `class_name` is the name of the class that the method belongs to, and `hierarchy` is used to
+    /// compute the method's `StackMapTable`.
+    pub fn emit(
+        self,
+        constant_pool: &mut ConstantPool,
+        class_name: &str,
+        hierarchy: &dyn ClassHierarchy,
+    ) -> Result<Vec<u8>, Error> {
         let mut bytes = Vec::new();
 
+        let is_static = self.access_flags.contains(&AccessFlag::Static);
         bytes.extend_from_slice(
             &self
                 .access_flags
@@ -72,17 +87,39 @@ impl MethodBuilder {
                 .to_be_bytes(),
         );
 
-        let name_index = constant_pool.insert_string(self.name.unwrap());
+        let name = self.name.unwrap();
+        let name_index = constant_pool.insert_string(name.clone());
         bytes.extend_from_slice(&name_index.to_be_bytes());
 
+        let return_descriptor = self.return_descriptor.unwrap_or("V".to_string());
+        let code = self.code.unwrap();
+        let expected = instruction::return_value(&return_descriptor);
+        if let Some(instruction) = code
+            .code()
+            .iter()
+            .find(|instruction| instruction.is_return() && **instruction != expected)
+        {
+            return Err(Error::ReturnTypeMismatch(
+                Box::new(instruction.clone()),
+                return_descriptor,
+            ));
+        }
+
         let descriptor_index = constant_pool.insert_string(format!(
             "({}){}",
             self.parameter_descriptors.join(""),
-            self.return_descriptor.unwrap_or("V".to_string())
+            return_descriptor
         ));
         bytes.extend_from_slice(&descriptor_index.to_be_bytes());
 
-        let attributes = vec![self.code.unwrap().build(constant_pool)?];
+        let method = MethodContext {
+            class_name,
+            name: &name,
+            parameters: &self.parameter_descriptors,
+            is_static,
+            hierarchy,
+        };
+        let attributes = vec![code.build(constant_pool, &method)?];
 
         bytes.extend_from_slice(&(attributes.len() as u16).to_be_bytes());
 